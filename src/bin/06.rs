@@ -1,11 +1,15 @@
+use advent_of_code::helpers::image::{self, FrameWriter, ImageFormat};
 use fancy_regex::Regex;
-use std::collections::{HashSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::process;
 
 type Command<'a> = (&'a str, [u32; 2], [u32; 2]);
-type LightsPart1 = HashSet<(u32, u32)>;
-type LightsPart2 = HashMap<(u32, u32), u32>;
+type LightsPart1 = Vec<Vec<bool>>;
+type LightsPart2 = Vec<Vec<u32>>;
 
-fn parse_command(line: &str) -> Command {
+const GRID_SIZE: usize = 1000;
+
+fn parse_command(line: &str) -> Command<'_> {
     let line_regex = Regex::new(r"(turn on|turn off|toggle) (\d+,\d+) through (\d+,\d+)").unwrap();
     let parse_number = |num_str: &str| num_str.parse::<u32>().unwrap();
 
@@ -24,59 +28,55 @@ fn parse_command(line: &str) -> Command {
     (cmd, from, to)
 }
 
-fn turn_on(lights: &mut LightsPart1, [from_y, from_x]: [u32; 2], [to_y, to_x]: [u32; 2]) {
+fn turn_on(lights: &mut LightsPart1, [from_x, from_y]: [u32; 2], [to_x, to_y]: [u32; 2]) {
     for y in from_y..(to_y+1) {
         for x in from_x..(to_x+1) {
-            lights.insert((x, y));
+            lights[y as usize][x as usize] = true;
         }
     }
 }
-fn turn_off(lights: &mut LightsPart1, [from_y, from_x]: [u32; 2], [to_y, to_x]: [u32; 2]) {
+fn turn_off(lights: &mut LightsPart1, [from_x, from_y]: [u32; 2], [to_x, to_y]: [u32; 2]) {
     for y in from_y..(to_y+1) {
         for x in from_x..(to_x+1) {
-            lights.remove(&(x, y));
+            lights[y as usize][x as usize] = false;
         }
     }
 }
-fn toggle(lights: &mut LightsPart1, [from_y, from_x]: [u32; 2], [to_y, to_x]: [u32; 2]) {
+fn toggle(lights: &mut LightsPart1, [from_x, from_y]: [u32; 2], [to_x, to_y]: [u32; 2]) {
     for y in from_y..(to_y+1) {
         for x in from_x..(to_x+1) {
-            if lights.contains(&(x, y)) {
-                lights.remove(&(x, y));
-            } else {
-                lights.insert((x, y));
-            }
+            let light = &mut lights[y as usize][x as usize];
+            *light = !*light;
         }
     }
 }
 
 fn increase(
     lights: &mut LightsPart2,
-    [from_y, from_x]: [u32; 2],
-    [to_y, to_x]: [u32; 2],
+    [from_x, from_y]: [u32; 2],
+    [to_x, to_y]: [u32; 2],
     increase_amnt: u32
 ) {
     for y in from_y..(to_y+1) {
         for x in from_x..(to_x+1) {
-             *lights.get_mut(&(x, y)).unwrap() += increase_amnt;
+            lights[y as usize][x as usize] += increase_amnt;
         }
     }
 }
 
-fn decrease(lights: &mut LightsPart2, [from_y, from_x]: [u32; 2], [to_y, to_x]: [u32; 2]) {
+fn decrease(lights: &mut LightsPart2, [from_x, from_y]: [u32; 2], [to_x, to_y]: [u32; 2]) {
     for y in from_y..(to_y+1) {
         for x in from_x..(to_x+1) {
-            lights.entry((x, y))
-                .and_modify(|pos| if *pos > 0 { *pos -= 1 });
+            let light = &mut lights[y as usize][x as usize];
+            *light = light.saturating_sub(1);
         }
     }
 }
 
-
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut lights: LightsPart1 = HashSet::new();
-
-    input.trim_end().split("\n").map(parse_command)
+/// Runs every instruction, handing the grid to `observe` after each one.
+fn simulate_part_one(input: &str, mut observe: impl FnMut(&LightsPart1)) -> LightsPart1 {
+    let mut lights: LightsPart1 = vec![vec![false; GRID_SIZE]; GRID_SIZE];
+    input.trim_end().split('\n').map(parse_command)
         .for_each(|(cmd, from, to)| {
             match cmd {
                 "turn on" => turn_on(&mut lights, from, to),
@@ -84,18 +84,14 @@ pub fn part_one(input: &str) -> Option<u32> {
                 "toggle" => toggle(&mut lights, from, to),
                 _ => panic!("unexpected command")
             }
+            observe(&lights);
         });
-    Some(lights.len() as u32)
+    lights
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut lights: LightsPart2 = HashMap::new();
-    for y in 0..1000 {
-        for x in 0..1000 {
-            lights.insert((x, y), 0);
-        }
-    }
-    input.trim_end().split("\n").map(parse_command)
+fn simulate_part_two(input: &str, mut observe: impl FnMut(&LightsPart2)) -> LightsPart2 {
+    let mut lights: LightsPart2 = vec![vec![0; GRID_SIZE]; GRID_SIZE];
+    input.trim_end().split('\n').map(parse_command)
         .for_each(|(cmd, from, to)| {
             match cmd {
                 "turn on" => increase(&mut lights, from, to, 1),
@@ -103,14 +99,81 @@ pub fn part_two(input: &str) -> Option<u32> {
                 "toggle" => increase(&mut lights, from, to, 2),
                 _ => panic!("unexpected command")
             }
+            observe(&lights);
         });
-    Some(lights.values().sum())
+    lights
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    let lights = simulate_part_one(input, |_| {});
+    Some(lights.iter().flatten().filter(|light| **light).count() as u32)
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let lights = simulate_part_two(input, |_| {});
+    Some(lights.iter().flatten().sum())
+}
+
+struct Args {
+    export: Option<PathBuf>,
+    frames: bool,
+    format: ImageFormat,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        export: args.opt_value_from_str("--export")?,
+        frames: args.contains("--frames"),
+        format: args.opt_value_from_str("--format")?.unwrap_or(ImageFormat::Pgm),
+    })
+}
+
+/// Writes `part1.<ext>` / `part2.<ext>` with the final lights into `dir`,
+/// plus one frame per instruction under `dir/part1/` and `dir/part2/` when asked.
+fn export(input: &str, dir: &Path, frames: bool, format: ImageFormat) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut writer_1 = frames.then(|| FrameWriter::new(dir.join("part1"), format)).transpose()?;
+    let mut writer_2 = frames.then(|| FrameWriter::new(dir.join("part2"), format)).transpose()?;
+    let mut frame_error = None;
+
+    let lights = simulate_part_one(input, |lights| {
+        if let (Some(writer), None) = (writer_1.as_mut(), &frame_error) {
+            frame_error = writer.write(lights).err();
+        }
+    });
+    if let Some(e) = frame_error.take() { return Err(e) }
+    image::write_image(dir.join(format!("part1.{}", format.extension())), &lights, format)?;
+
+    let lights = simulate_part_two(input, |lights| {
+        if let (Some(writer), None) = (writer_2.as_mut(), &frame_error) {
+            frame_error = writer.write(lights).err();
+        }
+    });
+    if let Some(e) = frame_error.take() { return Err(e) }
+    image::write_image(dir.join(format!("part2.{}", format.extension())), &lights, format)
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 6);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if let Some(dir) = args.export {
+        if let Err(e) = export(input, &dir, args.frames, args.format) {
+            eprintln!("failed to export lights: {e}");
+            process::exit(1);
+        }
+        println!("🎄 Wrote lights to \"{}\".", dir.display());
+    }
 }
 
 #[cfg(test)]
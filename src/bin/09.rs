@@ -1,53 +1,86 @@
-use std::collections::{HashMap, HashSet};
-use itertools::Itertools;
+use advent_of_code::helpers::graph::WeightedGraph;
+use advent_of_code::helpers::tsp::{self, Cost, Mode, Objective};
+use lazy_static::lazy_static;
 use regex::Regex;
+use std::process;
 
-type Edge<'a> = (&'a str, &'a str, u32);
+lazy_static! {
+    static ref EDGE_REGEX: Regex = Regex::new(r"^(\w+) to (\w+) = (\d+)$").unwrap();
+}
 
-fn parse_edge(line: &str) -> Edge {
-    let edge_regex = Regex::new(r"(\w+) to (\w+) = (\d+)").unwrap();
-    let captured = edge_regex.captures(line).unwrap();
-    (
-        captured.get(1).unwrap().as_str(),
-        captured.get(2).unwrap().as_str(),
-        captured.get(3).unwrap().as_str().parse::<u32>().unwrap(),
-    )
+fn parse_edge(line: &str) -> Option<(&str, &str, Cost)> {
+    let captured = EDGE_REGEX.captures(line)?;
+    Some((
+        captured.get(1)?.as_str(),
+        captured.get(2)?.as_str(),
+        captured.get(3)?.as_str().parse().ok()?,
+    ))
 }
 
-fn solve(edges: Vec<Edge>) -> Vec<u32> {
-    let mut nodes_name = HashSet::new();
-    let mut nodes_len_map = HashMap::new();
-    for (from, to, weight) in edges {
-        nodes_len_map.insert((from, to), weight);
-        nodes_len_map.insert((to, from), weight);
-        nodes_name.insert(from);
-        nodes_name.insert(to);
-    }
+fn parse_graph(input: &str) -> Option<WeightedGraph> {
+    WeightedGraph::parse_with(input, false, parse_edge).map_err(|e| eprintln!("{e}")).ok()
+}
 
-    let mut result_list = vec![];
-    for entry in nodes_name.clone().into_iter().combinations(nodes_name.len()) {
-        let mut tmp = 0;
-        for idx in 0..entry.len() - 1 {
-            let pair = entry[idx..idx + 2].to_vec();
-            tmp += nodes_len_map[&(pair[0], pair[1])];
+fn solve(input: &str, objective: Objective) -> Option<u32> {
+    let matrix = parse_graph(input)?.distance_matrix(None);
+    match tsp::solve(&matrix, Mode::Path, objective) {
+        Ok(tour) => u32::try_from(tour.cost).map_err(|_| eprintln!("cost {} does not fit in u32", tour.cost)).ok(),
+        Err(e) => {
+            eprintln!("{e}");
+            None
         }
-        result_list.push(tmp);
     }
-    result_list
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let edge_list: Vec<Edge> = input.trim_end().split("\n").map(parse_edge).collect();
-    Some(solve(edge_list).into_iter().min().unwrap())
+    solve(input, Objective::Min)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let edge_list: Vec<Edge> = input.trim_end().split("\n").map(parse_edge).collect();
-    Some(solve(edge_list).into_iter().max().unwrap())
+    solve(input, Objective::Max)
+}
+
+struct Args {
+    top: Option<usize>,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        top: args.opt_value_from_str("--top")?,
+    })
+}
+
+/// The `k` shortest and longest routes with the distance of every leg.
+fn print_routes(graph: &WeightedGraph, k: usize) -> Result<(), tsp::TspError> {
+    let matrix = graph.distance_matrix(None);
+    for (title, objective) in [("shortest", Objective::Min), ("longest", Objective::Max)] {
+        println!("🎄 {k} {title} routes 🎄");
+        for (rank, tour) in tsp::ranked(&matrix, Mode::Path, objective, k)?.iter().enumerate() {
+            print!("#{} {}", rank + 1, tour.describe(&matrix, graph.names()));
+        }
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 9);
+    if let Some(k) = args.top {
+        let Some(graph) = parse_graph(input) else { process::exit(1) };
+        if let Err(e) = print_routes(&graph, k) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -67,4 +100,15 @@ mod tests {
         let input = advent_of_code::read_file("examples", 9);
         assert_eq!(part_two(&input).unwrap(), 982);
     }
+
+    #[test]
+    fn test_routes() {
+        let input = advent_of_code::read_file("examples", 9);
+        let graph = parse_graph(&input).unwrap();
+        let matrix = graph.distance_matrix(None);
+        let tours = tsp::ranked(&matrix, Mode::Path, Objective::Min, 3).unwrap();
+        assert_eq!(tours.iter().map(|tour| tour.cost).collect::<Vec<_>>(), [605, 659, 982]);
+        let text = tours[0].describe(&matrix, graph.names());
+        assert!(text.contains("Dublin -> Belfast: +141") || text.contains("Belfast -> Dublin: +141"));
+    }
 }
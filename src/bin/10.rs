@@ -1,37 +1,46 @@
-fn expand(str: String) -> String {
-    let mut res: Vec<(char, u32)> = vec![];
-    for char in str.chars() {
-        if res.last().is_none() || res.last().unwrap().0 != char {
-            res.push((char, 1));
-        } else {
-            res.len();
-            res.last_mut().unwrap().1 += 1;
-        }
-    }
+use advent_of_code::helpers::look_and_say;
+use std::process;
 
-    res.into_iter().fold("".to_string(), |acc, (char, count)| {
-        acc + &count.to_string() + &char.to_string()
-    })
+fn length_after(input: &str, rounds: usize) -> Option<u32> {
+    look_and_say::length_after(input.trim_end(), rounds).and_then(|len| u32::try_from(len).ok())
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let mut res = input.trim_end().to_string();
-    for _ in 0..40 {
-        res = expand(res);
-    }
-    Some(res.len() as u32)
+    length_after(input, 40)
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let mut res = input.trim_end().to_string();
-    for _ in 0..50 {
-        res = expand(res);
-    }
-    Some(res.len() as u32)
+    length_after(input, 50)
+}
+
+struct Args {
+    rounds: Option<usize>,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        rounds: args.opt_value_from_str("--rounds")?,
+    })
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 10);
+    if let Some(rounds) = args.rounds {
+        match look_and_say::length_after(input.trim_end(), rounds) {
+            Some(len) => println!("🎄 Length after {rounds} rounds: {len}"),
+            None => println!("🎄 Length after {rounds} rounds does not fit in 128 bits"),
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -1,60 +1,99 @@
-use regex::Regex;
-use serde_json::{Map, Value, from_str};
+use advent_of_code::helpers::json::query::Query;
+use advent_of_code::helpers::json::{Prune, Walker};
+use serde_json::{from_str, Value};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::process;
 
-pub fn part_one(input: &str) -> Option<i32> {
-    let input = input.trim_end();
-    let num_regex = Regex::new(r"-?\d+").unwrap();
-    let res = num_regex.find_iter(input).fold(0, |acc, found| {
-        acc + found.as_str().parse::<i32>().unwrap()
-    });
-    Some(res)
+fn sum(input: &str, walker: Walker) -> Option<i32> {
+    let json: Value = from_str(input.trim_end()).map_err(|e| eprintln!("{e}")).ok()?;
+    let total = walker.sum(&json);
+    i32::try_from(total).map_err(|_| eprintln!("sum {total} does not fit in i32")).ok()
 }
 
-fn remove_red(json: &Value, re: &Regex) -> Value {
-    match json {
-        Value::Object(obj) => {
-            let mut new_obj = Map::new();
-            for (key, value) in obj {
-                match value {
-                    Value::String(str) => {
-                        if !re.is_match(str.as_str()) {
-                            new_obj.insert(key.clone(), value.clone());
-                        } else {
-                            return Value::Null;
-                        }
-                    },
-                    other => {
-                        new_obj.insert(key.clone(), remove_red(&other, re));
-                    },
-                };
-            }
-            Value::Object(new_obj)
-        }
-        Value::Array(arr) => {
-            let mut new_arr = Vec::new();
-            for item in arr {
-                new_arr.push(remove_red(item, re));
-            }
-            Value::Array(new_arr)
-        }
-        other => other.clone(),
-    }
+/// Objects with any value equal to "red" count for nothing, and neither does
+/// anything inside them.
+fn ignore_red() -> Walker {
+    Walker::new().prune(Prune::ObjectValue("red".to_string()))
+}
+
+pub fn part_one(input: &str) -> Option<i32> {
+    sum(input, Walker::new())
 }
 
 pub fn part_two(input: &str) -> Option<i32> {
-    let red_regex = Regex::new(r"red").unwrap();
-    let json: Value = from_str(input.trim_end()).unwrap();
-    let filtered_json = remove_red(&json, &red_regex);
+    sum(input, ignore_red())
+}
+
+struct Args {
+    stream: Option<PathBuf>,
+    queries: Vec<String>,
+    file: Option<PathBuf>,
+}
 
-    let num_regex = Regex::new(r"-?\d+").unwrap();
-    let res = num_regex.find_iter(&filtered_json.to_string()).fold(0, |acc, found| {
-        acc + found.as_str().parse::<i32>().unwrap()
-    });
-    Some(res)
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        stream: args.opt_value_from_os_str("--stream", |path| Ok::<_, String>(PathBuf::from(path)))?,
+        queries: args.values_from_str(["-q", "--query"])?,
+        file: args.opt_value_from_os_str("--file", |path| Ok::<_, String>(PathBuf::from(path)))?,
+    })
+}
+
+/// Both answers for a file too big to hold as a tree.
+fn stream(path: &PathBuf) -> Result<(), String> {
+    for (part, walker) in [(1, Walker::new()), (2, ignore_red())] {
+        let file = File::open(path).map_err(|e| format!("{}: {e}", path.display()))?;
+        let total = walker.sum_reader(BufReader::new(file)).map_err(|e| e.to_string())?;
+        println!("🎄 Part {part}: {total}");
+    }
+    Ok(())
+}
+
+/// Runs every query against `input`, printing what each one matched.
+fn run_queries(input: &str, queries: &[String]) -> Result<(), String> {
+    let queries = queries.iter().map(|text| Query::parse(text)).collect::<Result<Vec<_>, _>>().map_err(|e| e.to_string())?;
+    let json: Value = from_str(input.trim_end()).map_err(|e| e.to_string())?;
+    for query in queries {
+        print!("{}", query.run(&json));
+    }
+    Ok(())
 }
 
 fn main() {
-    let input = &advent_of_code::read_file("inputs", 12);
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
+    if let Some(path) = args.stream {
+        if let Err(e) = stream(&path) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
+    let input = &match &args.file {
+        Some(path) => match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("{}: {e}", path.display());
+                process::exit(1);
+            }
+        },
+        None => advent_of_code::read_file("inputs", 12),
+    };
+    if !args.queries.is_empty() {
+        if let Err(e) = run_queries(input, &args.queries) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -92,5 +131,18 @@ mod tests {
         assert_eq!(part_two(&lines[8]).unwrap(), 4);
         assert_eq!(part_two(&lines[9]).unwrap(), 0);
         assert_eq!(part_two(&lines[10]).unwrap(), 6);
+        // only the exact string "red" counts
+        assert_eq!(part_two(r#"[1,{"c":"reddish","b":2},3]"#).unwrap(), 6);
+    }
+
+    #[test]
+    fn test_queries() {
+        let input = advent_of_code::read_file("examples", 12);
+        let lines: Vec<&str> = input.trim_end().split('\n').collect();
+        let json: Value = from_str(lines[9]).unwrap();
+        let query = Query::parse(r#"sum $..* is number prune-value "red""#).unwrap();
+        assert_eq!(query.run(&json).sum() as i32, part_two(lines[9]).unwrap());
+        let paths = Query::parse("paths $..* is number").unwrap().run(&json).to_string();
+        assert_eq!(paths, "$.e[0]\n$.e[1]\n$.e[2]\n$.e[3]\n$.f\n");
     }
 }
@@ -1,81 +1,99 @@
-use std::collections::{HashSet, HashMap};
-use itertools::Itertools;
+use advent_of_code::helpers::graph::WeightedGraph;
+use advent_of_code::helpers::tsp::{self, Cost, DistanceMatrix, Mode, Objective};
+use lazy_static::lazy_static;
 use regex::Regex;
+use std::process;
 // use advent_of_code::debug;
 
-#[derive(Debug)]
-struct Stmt {
-    val: i32,
-    pair: (String, String),
+lazy_static! {
+    static ref SENTENCE_REGEX: Regex = Regex::new(
+        r#"^(?<from>\w+) would (?<ops>gain|lose) (?<val>\d+) happiness units by sitting next to (?<to>\w+)\.$"#
+    ).unwrap();
 }
 
-fn parse_sentence(str: &str) -> Stmt {
-    let sentence_regex = Regex::new(
-        r#"(?<from>\w+) would (?<ops>\w+) (?<val>\d+) happiness units by sitting next to (?<to>\w+)."#
-    ).unwrap();
-    let captured = sentence_regex.captures(str).unwrap();
-    let ops = match captured.name("ops").unwrap().as_str() {
-        "gain" => |e: i32| e,
-        "lose" => |e: i32| -e,
-        _ => panic!("line doesn't contain a word gain or lose")
-    };
-    Stmt {
-        val: ops(captured.name("val").unwrap().as_str().parse::<i32>().unwrap()),
-        pair: (
-            captured.name("from").unwrap().as_str().to_string(),
-            captured.name("to").unwrap().as_str().to_string(),
-        ),
-    }
+fn parse_sentence(str: &str) -> Option<(&str, &str, Cost)> {
+    let captured = SENTENCE_REGEX.captures(str)?;
+    let val = captured.name("val")?.as_str().parse::<Cost>().ok()?;
+    Some((
+        captured.name("from")?.as_str(),
+        captured.name("to")?.as_str(),
+        if &captured["ops"] == "lose" { -val } else { val },
+    ))
 }
 
-fn calculate(attendees: &HashSet<String>, val_map: &HashMap<(String, String), i32>) -> i32 {
-    let mut max = std::i32::MIN;
-    attendees.iter().permutations(attendees.len()).for_each(|perm| {
-        let mut tmp_max = 0;
-        for idx in 0..perm.len() {
-            if idx == perm.len()-1 {
-                tmp_max += val_map.get(&(perm[idx].to_string(), perm[0].to_string())).unwrap();
-                tmp_max += val_map.get(&(perm[0].to_string(), perm[idx].to_string())).unwrap();
-            } else {
-                tmp_max += val_map.get(&(perm[idx].to_string(), perm[idx+1].to_string())).unwrap();
-                tmp_max += val_map.get(&(perm[idx+1].to_string(), perm[idx].to_string())).unwrap();
-            }
+/// Everyone's feelings about their neighbours, plus `extra` guests who feel
+/// nothing either way about anyone.
+fn parse_graph(input: &str, extra: &[&str]) -> Option<WeightedGraph> {
+    let mut graph = WeightedGraph::parse_with(input, true, parse_sentence).map_err(|e| eprintln!("{e}")).ok()?;
+    extra.iter().for_each(|name| { graph.add_node(name); });
+    Some(graph)
+}
+
+/// Happiness of sitting `a` next to `b`, counted for both of them.
+fn happiness(graph: &WeightedGraph) -> DistanceMatrix {
+    graph.distance_matrix(Some(0)).pair_sums()
+}
+
+fn calculate(input: &str, extra: &[&str]) -> Option<u32> {
+    match tsp::solve(&happiness(&parse_graph(input, extra)?), Mode::Cycle, Objective::Max) {
+        Ok(tour) => u32::try_from(tour.cost).map_err(|_| eprintln!("cost {} does not fit in u32", tour.cost)).ok(),
+        Err(e) => {
+            eprintln!("{e}");
+            None
         }
-        max = std::cmp::max(tmp_max, max);
-    });
-    max
+    }
 }
 
 pub fn part_one(input: &str) -> Option<u32> {
-    let mut attendees: HashSet<String> = HashSet::new();
-    let mut val_map: HashMap<(String, String), i32> = HashMap::new();
-    let data: Vec<Stmt> = input.trim_end().split("\n").map(parse_sentence).collect();
-    data.iter().for_each(|info| {
-        attendees.insert(info.pair.0.clone());
-        attendees.insert(info.pair.1.clone());
-        val_map.insert((info.pair.0.clone(), info.pair.1.clone()), info.val.clone());
-    });
-    Some(calculate(&attendees, &val_map) as u32)
+    calculate(input, &[])
 }
 
 pub fn part_two(input: &str) -> Option<u32> {
-    let mut attendees: HashSet<String> = HashSet::new();
-    attendees.insert("you".to_string());
-
-    let mut val_map: HashMap<(String, String), i32> = HashMap::new();
-    let data: Vec<Stmt> = input.trim_end().split("\n").map(parse_sentence).collect();
-    data.iter().for_each(|info| {
-        attendees.insert(info.pair.0.clone());
-        attendees.insert(info.pair.1.clone());
-        val_map.insert((info.pair.0.clone(), info.pair.1.clone()), info.val.clone());
-        val_map.insert((info.pair.0.clone(), "you".to_string()), 0);
-        val_map.insert(("you".to_string(), info.pair.1.clone()), 0);
-    });
-    Some(calculate(&attendees, &val_map) as u32)
+    calculate(input, &["you"])
+}
+
+struct Args {
+    top: Option<usize>,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        top: args.opt_value_from_str("--top")?,
+    })
+}
+
+/// The `k` happiest seatings for both parts, with what every pair of neighbours adds.
+fn print_seatings(input: &str, k: usize) -> Result<(), String> {
+    for (part, extra) in [(1, &[][..]), (2, &["you"][..])] {
+        let graph = parse_graph(input, extra).ok_or("cannot read the guest list")?;
+        let matrix = happiness(&graph);
+        println!("🎄 Part {part}: {k} happiest seatings 🎄");
+        let tours = tsp::ranked(&matrix, Mode::Cycle, Objective::Max, k).map_err(|e| e.to_string())?;
+        for (rank, tour) in tours.iter().enumerate() {
+            print!("#{} {}", rank + 1, tour.describe(&matrix, graph.names()));
+        }
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 13);
+    if let Some(k) = args.top {
+        if let Err(e) = print_seatings(input, k) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -95,4 +113,16 @@ mod tests {
         let input = advent_of_code::read_file("examples", 13);
         assert_eq!(part_two(&input).unwrap(), 286);
     }
+
+    #[test]
+    fn test_seatings() {
+        let input = advent_of_code::read_file("examples", 13);
+        let graph = parse_graph(&input, &[]).unwrap();
+        let matrix = happiness(&graph);
+        let tours = tsp::ranked(&matrix, Mode::Cycle, Objective::Max, 3).unwrap();
+        assert_eq!(tours.len(), 3);
+        assert_eq!(tours[0].legs(&matrix).iter().map(|leg| leg.2).sum::<Cost>(), 330);
+        assert!(tours[0].describe(&matrix, graph.names()).starts_with("330: Alice -> "));
+        assert_eq!(parse_sentence("Bob would lose 7 happiness units by sitting next to Carol."), Some(("Bob", "Carol", -7)));
+    }
 }
@@ -1,111 +1,99 @@
-// use advent_of_code::debug;
-use std::{cmp, collections::HashSet};
-use regex::Regex;
+use advent_of_code::helpers::race::{Race, RaceError};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
 
-fn parse_line(line: &str) -> Deer {
-    let line_regex = Regex::new(
-        r#"(?<name>\w+) can fly (?<speed>\d+) km/s for (?<time_sprint>\d+) seconds, but then must rest for (?<time_rest>\d+) seconds."#
-    ).unwrap();
-    let captured = line_regex.captures(line).unwrap();
-    Deer::new(
-        captured.name("name").unwrap().as_str().to_string(),
-        captured.name("speed").unwrap().as_str().parse::<u32>().unwrap(),
-        captured.name("time_sprint").unwrap().as_str().parse::<u32>().unwrap(),
-        captured.name("time_rest").unwrap().as_str().parse::<u32>().unwrap(),
-    )
+const DURATION: u32 = 2503;
+
+fn race(input: &str, duration: u32) -> Option<Race> {
+    Race::parse(input, duration).map_err(|e| eprintln!("{e}")).ok()
 }
 
-#[derive(Debug)]
-struct Deer {
-    name: String,
-    status: u8, // 1 for sprint, 0 for rest
-    speed: u32,
-    time_sprint: u32,
-    time_rest: u32,
-    phase_time_left: u32,
-    travelled_km: u32,
-    point: u32,
+fn report<T>(result: Result<T, RaceError>) -> Option<T> {
+    result.map_err(|e| eprintln!("{e}")).ok()
 }
 
-impl Deer {
-    fn new(name: String, speed: u32, time_sprint: u32, time_rest: u32) -> Deer {
-        Deer {
-            name, speed, time_sprint, time_rest,
-            status: 1, phase_time_left: time_sprint, travelled_km: 0, point: 0
-        }
-    }
+pub fn part_one(input: &str) -> Option<u32> {
+    let distance = report(race(input, DURATION)?.winning_distance())?;
+    u32::try_from(distance).map_err(|_| eprintln!("distance {distance} does not fit in u32")).ok()
+}
 
-    fn get_distance(&self) -> u32 { self.travelled_km }
-    fn get_point(&self) -> u32 { self.point }
-    fn increase_point(&mut self) -> () { self.point += 1; }
+pub fn part_two(input: &str) -> Option<u32> {
+    report(race(input, DURATION)?.points())?.into_iter().max()
+}
 
-    fn exec(&mut self) -> () {
-        match self.status {
-            1 => { // if sprinting
-                if self.phase_time_left > 0 {
-                    self.travelled_km += self.speed;
-                    self.phase_time_left -= 1;
-                    if self.phase_time_left == 0 {
-                        self.phase_time_left = self.time_rest;
-                        self.status = 0;
-                    }
-                } else {
-                    self.phase_time_left = self.time_rest;
-                }
-            },
-            0 => { // if resting
-                if self.phase_time_left > 0 {
-                    self.phase_time_left -= 1;
-                    if self.phase_time_left == 0 {
-                        self.phase_time_left = self.time_sprint;
-                        self.status = 1;
-                    }
-                } else {
-                    self.phase_time_left = self.time_sprint;
-                }
-            },
-            _ => panic!("unexpected status"),
-        }
-    }
+struct Args {
+    duration: u32,
+    timeline: bool,
+    csv: Option<PathBuf>,
 }
 
-fn find_leaders(deers: &Vec<Deer>) -> Vec<String> {
-    let mut max: u32 = 0;
-    deers.iter().for_each(|deer| max = cmp::max(max, deer.get_distance()));
-    deers.iter()
-        .filter(|&deer| deer.get_distance() == max)
-        .map(|deer| deer.name.clone())
-        .collect::<Vec<String>>()
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        duration: args.opt_value_from_str("--duration")?.unwrap_or(DURATION),
+        timeline: args.contains("--timeline"),
+        csv: args.opt_value_from_os_str("--csv", |path| Ok::<_, String>(PathBuf::from(path)))?,
+    })
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut deers: Vec<Deer> = input.trim_end().split("\n").map(parse_line).collect();
-    let time = 2503;
-    for _ in 0..time {
-        deers.iter_mut().for_each(|deer: &mut Deer| deer.exec());
+/// One line per second in which the lead changes hands.
+fn print_timeline(race: &Race) -> Result<(), RaceError> {
+    let mut previous = vec![];
+    for tick in race.timeline()? {
+        if tick.leaders == previous {
+            continue;
+        }
+        let names = tick.leaders.iter().map(|&idx| race.herd[idx].name.as_str()).collect::<Vec<_>>();
+        let points = race.herd.iter().zip(&tick.points)
+            .map(|(reindeer, points)| format!("{} {points}", reindeer.name))
+            .collect::<Vec<_>>();
+        println!("{:>6}s {} lead at {} km ({})", tick.second, names.join(" and "), tick.distances[tick.leaders[0]], points.join(", "));
+        previous = tick.leaders;
     }
-    let mut max = 0;
-    deers.iter().for_each(|deer| max = cmp::max(max, deer.get_distance()));
-    Some(max)
+    Ok(())
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut deers: Vec<Deer> = input.trim_end().split("\n").map(parse_line).collect();
-    let time = 2503;
-    for _ in 0..time {
-        deers.iter_mut().for_each(|deer: &mut Deer| deer.exec());
-        let leaders: HashSet<String> = HashSet::from_iter(find_leaders(&deers).into_iter());
-        deers.iter_mut().for_each(|deer: &mut Deer| {
-            if leaders.contains(&deer.name) { deer.increase_point(); }
-        });
+/// Prints the timeline, writes the CSV, or else sums the race up.
+fn show(race: &Race, args: &Args) -> Result<(), String> {
+    if args.timeline {
+        print_timeline(race).map_err(|e| e.to_string())?;
+    }
+    match &args.csv {
+        Some(path) if path.as_os_str() == "-" => print!("{}", race.to_csv().map_err(|e| e.to_string())?),
+        Some(path) => {
+            fs::write(path, race.to_csv().map_err(|e| e.to_string())?)
+                .map_err(|e| format!("Failed to write \"{}\": {e}", path.display()))?;
+            println!("🎄 Wrote {} seconds of the race to \"{}\".", race.duration, path.display());
+        },
+        None => (),
+    }
+    if !args.timeline && args.csv.is_none() {
+        let distance = race.winning_distance().map_err(|e| e.to_string())?;
+        let points = race.points().map_err(|e| e.to_string())?.into_iter().max().unwrap_or(0);
+        println!("🎄 After {}s: furthest {distance} km, most points {points}", race.duration);
     }
-    let mut max = 0;
-    deers.iter().for_each(|deer| max = cmp::max(max, deer.get_point()));
-    Some(max)
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 14);
+    if args.timeline || args.csv.is_some() || args.duration != DURATION {
+        let Some(race) = race(input, args.duration) else { process::exit(1) };
+        if let Err(e) = show(&race, &args) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -1,126 +1,90 @@
-use std::{iter::zip, cmp};
+use advent_of_code::helpers::recipe::{Optimizer, Pantry, Recipe, RecipeError};
+use std::process;
 
-// use advent_of_code::debug;
-use itertools::Itertools;
-use regex::Regex;
+const CALORIES: i64 = 500;
 
-#[allow(dead_code)]
-#[derive(Debug)]
-struct Ingredient {
-    name: String,
-    capacity: i32,
-    durability: i32,
-    flavor: i32,
-    texture: i32,
-    calories: i32,
-}
-
-impl Ingredient {
-    fn score(&self, proportion: u32) -> Vec<i32> {
-        [self.capacity, self.durability, self.flavor, self.texture]
-            .iter_mut()
-            .map(|p| *p * proportion as i32)
-            .collect_vec()
-    }
-
-    fn score_with_calories(&self, proportion: u32) -> Vec<i32> {
-        let mut result = self.score(proportion);
-        result.push(self.calories * proportion as i32);
-        result
-    }
-}
-
-fn parse_ingredient(line: &str) -> Ingredient {
-    let line_regex = Regex::new(
-        r#"(?<name>\w+): capacity (-?\d+), durability (-?\d+), flavor (-?\d+), texture (-?\d+), calories (-?\d+)"#
-    ).unwrap();
-    let captured = line_regex.captures(line).unwrap();
-    let (capacity, durability, flavor, texture, calories) = match (2..=6).map(|i| {
-        captured.get(i).unwrap().as_str().parse::<i32>().unwrap()
-    }).collect::<Vec<i32>>()[..] {
-        [a,b,c,d,e] => (a,b,c,d,e),
-        _ => panic!("unexpected parsed result"),
+fn best(input: &str, calories: Option<i64>) -> Result<Option<Recipe>, RecipeError> {
+    let pantry = Pantry::parse(input)?;
+    let optimizer = Optimizer::new(&pantry);
+    let optimizer = match calories {
+        Some(total) => optimizer.target("calories", total)?,
+        None => optimizer,
     };
-
-    Ingredient {
-        name: captured.name("name").unwrap().as_str().to_string(),
-        capacity, durability, flavor, texture, calories
-    }
+    Ok(optimizer.best())
 }
 
-fn find_combinations(sum: i32, n: usize) -> Vec<Vec<i32>> {
-    fn find_combinations_helper(
-        remaining_sum: i32,
-        remaining_count: usize,
-        current_combination: Vec<i32>,
-        all_combinations: &mut Vec<Vec<i32>>,
-    ) {
-        if remaining_count == 0 {
-            if remaining_sum == 0 {
-                all_combinations.push(current_combination);
-            }
-            return;
-        }
-
-        for i in 0..=remaining_sum {
-            let mut new_combination = current_combination.clone();
-            new_combination.push(i);
-            find_combinations_helper(
-                remaining_sum - i,
-                remaining_count - 1,
-                new_combination,
-                all_combinations,
-            );
+fn best_score(input: &str, calories: Option<i64>) -> Option<i64> {
+    match best(input, calories) {
+        Ok(recipe) => recipe.map(|recipe| recipe.score),
+        Err(e) => {
+            eprintln!("{e}");
+            None
         }
     }
-
-    let mut all_combinations = Vec::new();
-    find_combinations_helper(sum, n, Vec::new(), &mut all_combinations);
-    all_combinations
 }
 
-fn generate_data(input: &str) -> (Vec<Ingredient>, Vec<Vec<i32>>) {
-    let ingredients: Vec<Ingredient> = input.trim_end().split("\n").map(parse_ingredient).collect();
-    let combinations = find_combinations(100, ingredients.len());
-    (ingredients, combinations)
+pub fn part_one(input: &str) -> Option<i64> {
+    best_score(input, None)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let (ingredients, combinations) = generate_data(input);
-
-    let mut max = std::u32::MIN;
-    for combination in combinations {
-        let tmp: Vec<i32> = zip(combination, &ingredients).map(|(proportion, ingredient)| {
-            ingredient.score(proportion as u32)
-        }).reduce(|acc, item| zip(acc,item).map(|(a, b)| a+b ).collect_vec()).unwrap();
-
-        if tmp.iter().any(|ingredient| *ingredient <= 0) { continue; }
-        max = cmp::max(max, tmp.iter().product::<i32>() as u32);
-    }
-    Some(max)
+pub fn part_two(input: &str) -> Option<i64> {
+    best_score(input, Some(CALORIES))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let (ingredients, combinations) = generate_data(input);
+struct Args {
+    teaspoons: Option<u32>,
+    calories: Option<i64>,
+    score: Option<String>,
+}
 
-    let mut max = std::u32::MIN;
-    for combination in combinations {
-        let mut tmp: Vec<i32> = zip(combination, &ingredients).map(|(proportion, ingredient)| {
-            ingredient.score_with_calories(proportion as u32)
-        }).reduce(|acc, item| {
-            zip(acc,item).map(|(a, b)| a+b ).collect_vec()
-        }).unwrap();
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        teaspoons: args.opt_value_from_str("--teaspoons")?,
+        calories: args.opt_value_from_str("--calories")?,
+        score: args.opt_value_from_str("--score")?,
+    })
+}
 
-        if tmp.iter().any(|ingredient| *ingredient <= 0)
-            || *tmp.last().unwrap() != 500 { continue; }
-        tmp.pop();
-        max = cmp::max(max, tmp.iter().product::<i32>() as u32);
+/// Finds and prints the best recipe under the given parameters.
+fn optimize(input: &str, args: &Args) -> Result<(), RecipeError> {
+    let pantry = Pantry::parse(input)?;
+    let mut optimizer = Optimizer::new(&pantry).teaspoons(args.teaspoons.unwrap_or(100));
+    if let Some(properties) = &args.score {
+        optimizer = optimizer.score(&properties.split(',').map(str::trim).collect::<Vec<_>>())?;
+    }
+    if let Some(total) = args.calories {
+        optimizer = optimizer.target("calories", total)?;
     }
-    Some(max)
+    match optimizer.best() {
+        Some(recipe) => {
+            println!("🎄 Best recipe scores {}: {}", recipe.score, recipe.describe(&pantry));
+            for (property, total) in pantry.properties.iter().zip(&recipe.totals) {
+                println!("    {property}: {total}");
+            }
+        },
+        None => println!("🎄 No recipe meets the calorie target."),
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 15);
+    if args.teaspoons.is_some() || args.calories.is_some() || args.score.is_some() {
+        if let Err(e) = optimize(input, &args) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
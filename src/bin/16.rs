@@ -1,81 +1,102 @@
-use std::collections::HashMap;
+use advent_of_code::helpers::records::{Comparator, MatchError, Matcher, Record};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
 
-// use advent_of_code::debug;
-use itertools::Itertools;
-use regex::Regex;
-use lazy_static::lazy_static;
+/// What the MFCSAM read off the gift.
+const MFCSAM: &str = "children: 3
+cats: 7
+samoyeds: 2
+pomeranians: 3
+akitas: 0
+vizslas: 0
+goldfish: 5
+trees: 3
+cars: 2
+perfumes: 1
+";
 
-lazy_static! {
-    static ref THINGS: HashMap<&'static str, u32> = HashMap::from_iter([
-        ("children", 3),
-        ("cats", 7),
-        ("samoyeds", 2),
-        ("pomeranians", 3),
-        ("akitas", 0),
-        ("vizslas", 0),
-        ("goldfish", 5),
-        ("trees", 3),
-        ("cars", 2),
-        ("perfumes", 1),
-    ]);
-    static ref LINE_REGEX: Regex = Regex::new(
-        r"Sue (\d+): (\w+): (\d+), (\w+): (\d+), (\w+): (\d+)"
-    ).unwrap();
-    static ref GT: Vec<&'static str> = vec!["cats", "trees"];
-    static ref LT: Vec<&'static str> = vec!["pomeranians", "goldfish"];
+/// The retroencabulator's outdated readings.
+const RETROENCABULATOR: [(&str, Comparator); 4] = [
+    ("cats", Comparator::Gt),
+    ("trees", Comparator::Gt),
+    ("pomeranians", Comparator::Lt),
+    ("goldfish", Comparator::Lt),
+];
+
+fn find_sue(input: &str, comparators: &[(&str, Comparator)]) -> Result<Option<u32>, MatchError> {
+    let sues = Record::parse_all(input)?;
+    let matcher = comparators.iter()
+        .fold(Matcher::new(Record::parse_readout(MFCSAM)?), |matcher, &(property, comparator)| matcher.compare(property, comparator));
+    Ok(matcher.best(&sues).and_then(Record::number))
 }
 
-fn parse_line(line: &str) -> (u32, Vec<(String, u32)>) {
-    let captured = LINE_REGEX.captures(line).unwrap();
-    (
-        captured.get(1).unwrap().as_str().parse::<u32>().unwrap(),
-        (1..=3).into_iter().map(|i| {
-            (
-                captured.get(i*2).unwrap().as_str().to_string(),
-                captured.get((i*2)+1).unwrap().as_str().parse::<u32>().unwrap(),
-            )
-        }).collect_vec()
-    )
+fn report(result: Result<Option<u32>, MatchError>) -> Option<u32> {
+    result.map_err(|e| eprintln!("{e}")).ok().flatten()
 }
 
-fn is_match_property(props: &Vec<(String, u32)>) -> bool {
-    props.iter().all(|(key, val)| {
-        THINGS.get(key.as_str()).unwrap() == val
-    })
+pub fn part_one(input: &str) -> Option<u32> {
+    report(find_sue(input, &[]))
 }
 
-fn is_match_property_part2(props: &Vec<(String, u32)>) -> bool {
-    props.iter().all(|(key, val)| {
-        if GT.contains(&key.as_str()) {
-            val > THINGS.get(key.as_str()).unwrap()
-        } else if LT.contains(&key.as_str()) {
-            val < THINGS.get(key.as_str()).unwrap()
-        } else {
-            THINGS.get(key.as_str()).unwrap() == val
-        }
-    })
+pub fn part_two(input: &str) -> Option<u32> {
+    report(find_sue(input, &RETROENCABULATOR))
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let sues: Vec<(u32, Vec<(String, u32)>)> = input.trim_end().split("\n").map(parse_line).collect();
-    let the_one = sues.iter()
-        .find(|&(_, properties)| {
-            is_match_property(properties)
-        }).unwrap();
-    Some(the_one.0)
+struct Args {
+    readout: Option<PathBuf>,
+    compare: Vec<String>,
+    top: Option<usize>,
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let sues: Vec<(u32, Vec<(String, u32)>)> = input.trim_end().split("\n").map(parse_line).collect();
-    let the_one = sues.iter()
-        .find(|&(_, properties)| {
-            is_match_property_part2(properties)
-        }).unwrap();
-    Some(the_one.0)
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        readout: args.opt_value_from_os_str("--readout", |path| Ok::<_, String>(PathBuf::from(path)))?,
+        compare: args.values_from_str("--compare")?,
+        top: args.opt_value_from_str("--top")?,
+    })
+}
+
+/// Ranks every Sue against a readout file and `property=comparator` rules.
+fn rank(input: &str, args: &Args) -> Result<(), String> {
+    let readout = match &args.readout {
+        Some(path) => fs::read_to_string(path).map_err(|e| format!("Failed to read \"{}\": {e}", path.display()))?,
+        None => MFCSAM.to_string(),
+    };
+    let mut matcher = Matcher::new(Record::parse_readout(&readout).map_err(|e| e.to_string())?);
+    for rule in &args.compare {
+        let (property, comparator) = rule.split_once('=').ok_or_else(|| format!("expected `property=comparator`, got `{rule}`"))?;
+        matcher = matcher.compare(property, comparator.parse().map_err(|e: MatchError| e.to_string())?);
+    }
+    let sues = Record::parse_all(input).map_err(|e| e.to_string())?;
+    for candidate in matcher.rank(&sues).into_iter().take(args.top.unwrap_or(10)) {
+        let violated = match candidate.violated.is_empty() {
+            true => String::new(),
+            false => format!(", violates {}", candidate.violated.join(", ")),
+        };
+        println!("🎄 {}: {} matched, {} unknown{violated}", candidate.record.name, candidate.matched, candidate.unknown);
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 16);
+    if args.readout.is_some() || !args.compare.is_empty() || args.top.is_some() {
+        if let Err(e) = rank(input, &args) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -1,64 +1,64 @@
-// use advent_of_code::debug;
-use itertools::Itertools;
+use advent_of_code::helpers::subset_sum::SubsetSums;
+use std::process;
 
-fn find_combinations(target: u32, list: &Vec<u32>) -> Vec<Vec<u32>> {
-    fn backtracking(
-        avail_list: &Vec<u32>,
-        target: u32,
-        start: usize,
-        current_combination: Vec<u32>,
-        result: &mut Vec<Vec<u32>>
-    ) {
-        if target == 0 {
-            result.push(current_combination.clone());
-            return;
-        }
+fn liters() -> u64 {
+    if cfg!(test) { 25 } else { 150 }
+}
 
-        for idx in start..avail_list.len() {
-            if avail_list[idx] <= target {
-                let mut new_combination = current_combination.clone();
-                new_combination.push(avail_list[idx]);
-                backtracking(
-                    avail_list,
-                    target - avail_list[idx],
-                    idx+1,
-                    new_combination,
-                    result
-                );
-            }
-        }
-    }
+fn parse(input: &str) -> Vec<u64> {
+    input.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().parse::<u64>().unwrap())
+        .collect()
+}
+
+fn subset_sums(containers: &[u64], liters: u64) -> Option<SubsetSums> {
+    SubsetSums::new(containers, liters).map_err(|e| eprintln!("{e}")).ok()
+}
 
-    let mut result: Vec<Vec<u32>> = vec![];
-    backtracking(list, target, 0, vec![], &mut result);
-    result
-} 
+pub fn part_one(input: &str) -> Option<u64> {
+    Some(subset_sums(&parse(input), liters())?.count())
+}
 
-fn generate_data(input: &str) -> Vec<Vec<u32>> {
-    let target = if cfg!(test) { 25 } else { 150 };
-    let nums = input.trim_end().split("\n")
-        .map(|num| num.parse::<u32>().unwrap())
-        .collect_vec();
-    find_combinations(target, &nums)
+pub fn part_two(input: &str) -> Option<u64> {
+    let sums = subset_sums(&parse(input), liters())?;
+    Some(sums.count_of_size(sums.min_size()?))
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let result = generate_data(input);
-    // debug!(result);
-    Some(result.len() as u32)
+struct Args {
+    liters: Option<u64>,
+    list: bool,
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let result = generate_data(input);
-    let mut min = std::usize::MAX;
-    result.iter().for_each(|combi| {
-        min = std::cmp::min(min, combi.len());
-    });
-    Some(result.iter().filter(|combi| combi.len() == min).count() as u32)
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        liters: args.opt_value_from_str("--liters")?,
+        list: args.contains("--list"),
+    })
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 17);
+    if args.liters.is_some() || args.list {
+        let containers = parse(input);
+        let Some(sums) = subset_sums(&containers, args.liters.unwrap_or_else(liters)) else { return };
+        println!("🎄 {} combinations, {} of them with the fewest containers", sums.count(), sums.min_size().map_or(0, |size| sums.count_of_size(size)));
+        if args.list {
+            for combination in sums.by_size() {
+                println!("{:?}", combination.iter().map(|&idx| containers[idx]).collect::<Vec<_>>());
+            }
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -1,5 +1,8 @@
+use advent_of_code::helpers::image::{self, FrameWriter, ImageFormat};
 use itertools::Itertools;
 use lazy_static::lazy_static;
+use std::path::{Path, PathBuf};
+use std::process;
 // use advent_of_code::debug;
 
 type Map = Vec<Vec<bool>>;
@@ -62,7 +65,7 @@ fn update_map(map: &Map, part2: bool) -> Map {
                     }
                 },
                 false => {
-                    if *&neighbors.iter().filter(|n| **n).count() == 3  {
+                    if neighbors.iter().filter(|n| **n).count() == 3  {
                         new_map[idx_row][idx_col] = true;
                     }
                 }
@@ -72,42 +75,99 @@ fn update_map(map: &Map, part2: bool) -> Map {
     new_map
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let mut map = parse_map(input);
-    let step = if cfg!(test) { 4 } else { 100 }; 
-    (0..step).for_each(|_| {
-        map = update_map(&map, false);
-    });
-    Some(map.iter().fold(0, |acc, row| {
-        acc + row.iter().filter(|state| **state).count() as u32
-    }))
-}
-
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut map = parse_map(input);
+fn stuck_corners(map: &mut Map) {
     let b = map.len() - 1;
     map[0][0] = true;
     map[0][b] = true;
     map[b][b] = true;
     map[b][0] = true;
-    // print_map(&map);
-    // println!("-------------------------");
+}
 
-    let step = if cfg!(test) { 5 } else { 100 }; 
+/// Runs `step` generations, handing the initial map and every generation to `observe`.
+fn simulate(input: &str, step: usize, part2: bool, mut observe: impl FnMut(&Map)) -> Map {
+    let mut map = parse_map(input);
+    if part2 {
+        stuck_corners(&mut map);
+    }
+    observe(&map);
     (0..step).for_each(|_| {
-        map = update_map(&map, true);
-        // print_map(&map);
-        // println!("-------------------------");
+        map = update_map(&map, part2);
+        observe(&map);
     });
-    Some(map.iter().fold(0, |acc, row| {
+    map
+}
+
+fn count_on(map: &Map) -> u32 {
+    map.iter().fold(0, |acc, row| {
         acc + row.iter().filter(|state| **state).count() as u32
-    }))
+    })
+}
+
+pub fn part_one(input: &str) -> Option<u32> {
+    let step = if cfg!(test) { 4 } else { 100 };
+    Some(count_on(&simulate(input, step, false, |_| {})))
+}
+
+pub fn part_two(input: &str) -> Option<u32> {
+    let step = if cfg!(test) { 5 } else { 100 };
+    Some(count_on(&simulate(input, step, true, |_| {})))
+}
+
+struct Args {
+    export: Option<PathBuf>,
+    frames: bool,
+    format: ImageFormat,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        export: args.opt_value_from_str("--export")?,
+        frames: args.contains("--frames"),
+        format: args.opt_value_from_str("--format")?.unwrap_or(ImageFormat::Pbm),
+    })
+}
+
+/// Writes `part1.<ext>` / `part2.<ext>` with the last generation into `dir`,
+/// plus one frame per generation under `dir/part1/` and `dir/part2/` when asked.
+fn export(input: &str, dir: &Path, frames: bool, format: ImageFormat) -> std::io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for (part, part2) in [(1, false), (2, true)] {
+        let mut writer = frames.then(|| FrameWriter::new(dir.join(format!("part{part}")), format)).transpose()?;
+        let mut frame_error = None;
+        let map = simulate(input, 100, part2, |map| {
+            if let (Some(writer), None) = (writer.as_mut(), &frame_error) {
+                frame_error = writer.write(map).err();
+            }
+        });
+        if let Some(e) = frame_error {
+            return Err(e);
+        }
+        image::write_image(dir.join(format!("part{part}.{}", format.extension())), &map, format)?;
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 18);
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
+
+    if let Some(dir) = args.export {
+        if let Err(e) = export(input, &dir, args.frames, args.format) {
+            eprintln!("failed to export lights: {e}");
+            process::exit(1);
+        }
+        println!("🎄 Wrote lights to \"{}\".", dir.display());
+    }
 }
 
 #[cfg(test)]
@@ -141,6 +201,6 @@ mod tests {
 ####.#
         "###;
         // let input = advent_of_code::read_file("examples", 18);
-        assert_eq!(part_two(&input.trim()).unwrap(), 17);
+        assert_eq!(part_two(input.trim()).unwrap(), 17);
     }
 }
@@ -1,86 +1,122 @@
-use std::collections::{HashMap, HashSet};
+use advent_of_code::helpers::molecule::{self, Atom, MoleculeError, Replacements};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
 
-// use advent_of_code::debug;
-use itertools::Itertools;
-use lazy_static::lazy_static;
-use regex::Regex;
+const RESTARTS: usize = 1000;
 
-lazy_static! {
-    static ref REPLACEMENT_REGEX: Regex = Regex::new(r"(\w+) => (\w+)").unwrap();
-    static ref MOLECULE_REGEX: Regex = Regex::new(r"[A-Z][a-z]?").unwrap();
+fn parse(input: &str) -> Option<(Replacements, Vec<Atom>)> {
+    molecule::parse(input).map_err(|e: MoleculeError| eprintln!("{e}")).ok()
 }
 
-fn get_vec_molecule(molecule_str: &str) -> Vec<&str> {
-    MOLECULE_REGEX.find_iter(molecule_str)
-        .map(|found| found.as_str())
-        .collect_vec()
+pub fn part_one(input: &str) -> Option<u32> {
+    let (replacements, molecule) = parse(input)?;
+    Some(replacements.successors(&molecule).len() as u32)
 }
 
-fn parse_input(input: &str) -> (HashMap<&str, Vec<Vec<&str>>>, Vec<&str>) {
-    let mut replacements = input.trim_end().split("\n").collect_vec();
-    let starter = replacements.pop().unwrap();
-    replacements.pop();
-    let mut replacer_map: HashMap<&str, Vec<Vec<&str>>> = HashMap::new();
-    replacements.iter().for_each(|line| {
-        let replacement_captured = REPLACEMENT_REGEX.captures(line).unwrap();
-        replacer_map
-            .entry(replacement_captured.get(1).unwrap().as_str())
-            .or_insert(vec![])
-            .push(get_vec_molecule(replacement_captured.get(2).unwrap().as_str()));
-    });
-    (
-        replacer_map,
-        get_vec_molecule(starter)
-    )
+pub fn part_two(input: &str) -> Option<u32> {
+    let (replacements, molecule) = parse(input)?;
+    replacements.fewest_steps(&molecule).map(|steps| steps as u32)
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let (replacement, molecule) = parse_input(input);
-    let mut molecule_set: HashSet<String> = HashSet::new();
-
-    for idx in 0..molecule.len() {
-        match replacement.get(molecule[idx]) {
-            Some(replacers) => {
-                for replacer in replacers {
-                    molecule_set.insert(
-                        molecule.iter()
-                            .enumerate()
-                            .flat_map(|(i_enum, elem)| {
-                                if i_enum == idx { replacer.clone() } else { vec![*elem] }
-                            })
-                            .join("")
-                    );
-                }
-            },
-            None => ()
-        }
-    }
-    Some(molecule_set.len() as u32)
+struct Args {
+    check: bool,
+    trace: bool,
+    reverse: bool,
+    greedy: bool,
+    validate: Option<PathBuf>,
+    restarts: usize,
+    seed: u64,
 }
 
-fn formular(total_molecule: u32, bracket_amnt: u32, comma_amnt: u32) -> u32 {
-    total_molecule - bracket_amnt - (2*comma_amnt) - (
-        if cfg!(test) { 0 } else { 1 } // in test e => X not e => XX so increase one
-    )
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        check: args.contains("--check"),
+        trace: args.contains("--trace"),
+        reverse: args.contains("--reverse"),
+        greedy: args.contains("--greedy"),
+        validate: args.opt_value_from_os_str("--validate", |path| Ok::<_, String>(PathBuf::from(path)))?,
+        restarts: args.opt_value_from_str("--restarts")?.unwrap_or(RESTARTS),
+        seed: args.opt_value_from_str("--seed")?.unwrap_or(1),
+    })
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let mut replacements = input.trim_end().split("\n").collect_vec();
-    let target = replacements.pop().unwrap();
-    let molecule = get_vec_molecule(target);
-    let bracket_regex = Regex::new(r"(Rn|Ar)").unwrap();
-    let comma_regex = Regex::new(r"(Y)").unwrap();
+/// Solves part two the exact way, the greedy way and by the formula, and
+/// says whether they agree.
+fn check(input: &str, args: &Args) {
+    let Some((replacements, molecule)) = parse(input) else { process::exit(1) };
+    let show = |steps: Option<usize>| steps.map_or("none".to_string(), |steps| steps.to_string());
+    let fewest = replacements.fewest_steps(&molecule);
+    let greedy = replacements.reduce_greedy(&molecule, args.restarts, args.seed).map(|steps| steps.len());
+    let formula = replacements.formula(&molecule);
+    println!("🎄 CYK: {}", show(fewest));
+    println!("🎄 Greedy, {} restarts from seed {}: {}", args.restarts, args.seed, show(greedy));
+    println!("🎄 Formula: {}", formula.map_or("rules do not fit it".to_string(), |steps| steps.to_string()));
+    let disagree = [greedy, formula].into_iter().flatten().any(|steps| Some(steps) != fewest);
+    println!("🎄 {}", if disagree { "The answers disagree." } else { "The answers agree." });
+}
 
-    let bracket_amnt = bracket_regex.find_iter(target).count() as u32;
-    let comma_amnt = comma_regex.find_iter(target).count() as u32;
-    // debug!(bracket_amnt);
-    // debug!(comma_amnt);
+/// Prints a derivation one molecule per line, each with the step that made
+/// it, in a form `--validate` reads back.
+fn trace(input: &str, args: &Args) {
+    let Some((replacements, molecule)) = parse(input) else { process::exit(1) };
+    let steps = match args.greedy {
+        true => replacements.reduce_greedy(&molecule, args.restarts, args.seed),
+        false => replacements.shortest_derivation(&molecule),
+    };
+    let Some(steps) = steps else {
+        eprintln!("No derivation of the molecule found.");
+        process::exit(1);
+    };
+    let molecules = replacements.molecules(&steps).unwrap();
+    let mut lines = vec![format!("{}  # start", replacements.render(&molecules[0]))];
+    for (idx, (step, molecule)) in steps.iter().zip(&molecules[1..]).enumerate() {
+        lines.push(format!("{}  # {}. {}", replacements.render(molecule), idx + 1, replacements.describe(*step)));
+    }
+    if args.reverse {
+        lines.reverse();
+    }
+    lines.iter().for_each(|line| println!("{line}"));
+}
 
-    Some(formular(molecule.len() as u32, bracket_amnt, comma_amnt))
+fn validate(input: &str, path: &PathBuf) {
+    let Some((mut replacements, molecule)) = parse(input) else { process::exit(1) };
+    let text = fs::read_to_string(path).unwrap_or_else(|e| {
+        eprintln!("Failed to read \"{}\": {e}", path.display());
+        process::exit(1);
+    });
+    match replacements.check_derivation(&text, &molecule) {
+        Ok(steps) => println!("🎄 Valid derivation in {} steps.", steps.len()),
+        Err(e) => {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+    }
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 19);
+    if let Some(path) = &args.validate {
+        validate(input, path);
+        return;
+    }
+    if args.trace {
+        trace(input, &args);
+        return;
+    }
+    if args.check {
+        check(input, &args);
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
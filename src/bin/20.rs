@@ -1,70 +1,68 @@
-// use advent_of_code::debug;
-use std::sync::Mutex;
-use itertools::Itertools;
-use rayon::prelude::{IntoParallelIterator, ParallelIterator};
+use advent_of_code::helpers::presents::{Delivery, PresentsError};
+use std::process;
 
-fn find_divisible_numbers(divisor: u32, part2: bool) -> Vec<u32> {
-    let mut res: Vec<u32> = (1..=divisor/2)
-        .filter(|&x| {
-            divisor % x == 0 &&
-            if !part2 {
-                true
-            } else {
-                divisor / x <= 50
-            }
-        })
-        .collect_vec();
-    res.push(divisor);
-    res
+/// Part two elves stop after this many houses.
+const LAZY_LIMIT: u64 = 50;
+
+fn parse(input: &str) -> u64 {
+    input.trim().parse::<u64>().unwrap()
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let target = input.trim_end().parse::<u32>().unwrap() / 10;
+fn report<T>(result: Result<Option<T>, PresentsError>) -> Option<T> {
+    result.map_err(|e| eprintln!("{e}")).ok().flatten()
+}
 
-    let found = Mutex::new(target);
-    let test_range = if cfg!(test) {
-        1..10
-    }
-    else {
-        770_000..780_000
-    };
-    test_range.into_par_iter().for_each(|num| {
-        let tmp = find_divisible_numbers(num, false)
-            .iter().sum::<u32>();
-        // debug!((num, tmp));
-        if tmp >= target {
-            let mut found = found.lock().unwrap();
-            *found = std::cmp::min(num, *found);
-        }
-    });
-    Some(found.into_inner().unwrap())
+pub fn part_one(input: &str) -> Option<u64> {
+    report(Delivery::new(10).first_house(parse(input)))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let target = input.trim_end().parse::<u32>().unwrap();
+pub fn part_two(input: &str) -> Option<u64> {
+    report(Delivery::new(11).limit(LAZY_LIMIT).first_house(parse(input)))
+}
 
-    let found = Mutex::new(target);
-    let test_range = if cfg!(test) {
-        1..10
-    }
-    else {
-        750_000..800_000
-    };
-    test_range.into_par_iter().for_each(|num| {
-        let tmp = find_divisible_numbers(num, true)
-            .iter().map(|n| n*11).sum::<u32>();
-        // debug!((num, tmp));
-        if tmp >= target {
-            let mut found = found.lock().unwrap();
-            *found = std::cmp::min(num, *found);
-        }
-    });
-    Some(found.into_inner().unwrap())
+struct Args {
+    multiplier: Option<u64>,
+    limit: Option<u64>,
+    house: Option<u64>,
+}
 
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        multiplier: args.opt_value_from_str("--multiplier")?,
+        limit: args.opt_value_from_str("--limit")?,
+        house: args.opt_value_from_str("--house")?,
+    })
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 20);
+    if args.multiplier.is_some() || args.limit.is_some() || args.house.is_some() {
+        let mut delivery = Delivery::new(args.multiplier.unwrap_or(10));
+        if let Some(limit) = args.limit {
+            delivery = delivery.limit(limit);
+        }
+        let shown = match args.house {
+            Some(house) => delivery.presents(house).map(|presents| println!("🎄 House {house} gets {presents} presents.")),
+            None => delivery.first_house(parse(input)).map(|found| match found {
+                Some(house) => println!("🎄 House {house} is the first to get {} presents or more.", parse(input)),
+                None => println!("🎄 No house gets {} presents.", parse(input)),
+            }),
+        };
+        if let Err(e) = shown {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -1,108 +1,172 @@
-// use advent_of_code::debug;
-use std::collections::HashMap;
-use itertools::Itertools;
-use lazy_static::lazy_static;
-use regex::Regex;
+use advent_of_code::helpers::vm::{analysis, asm, isa, optimize, InstructionSet, Machine, Program, Register, Stop, Value, VmError};
+use std::process;
+use std::time::{Duration, Instant};
 
+fn exec(input: &str, start: Value) -> Result<Value, VmError> {
+    let mut machine = Machine::new(asm::assemble(input)?);
+    machine.set_kernels(optimize::optimize(machine.program()));
+    machine.set_register(Register::A, start);
+    machine.run()?;
+    Ok(machine.register(Register::B))
+}
 
-lazy_static! {
-    static ref INST_REGEX: Regex = Regex::new(r"^(?<inst>\w{3}) (?<reg>[ab])?((, )?(?<offset>[+-]\d+))?$").unwrap();
+fn report(result: Result<Value, VmError>) -> Option<u32> {
+    let b = result.map_err(|e| eprintln!("{e}")).ok()?;
+    u32::try_from(b).map_err(|_| eprintln!("b = {b} does not fit in u32")).ok()
 }
 
-#[derive(Debug)]
-struct Code {
-    inst: String,
-    register: usize,
-    offset: i32,
+pub fn part_one(input: &str) -> Option<u32> {
+    report(exec(input, 0))
 }
 
-fn parse_input(input: &str) -> Vec<Code> {
-    input.trim().split("\n").map(|line| {
-        INST_REGEX.captures(line)
-        .map(|captured| {
-            Code {
-                inst: captured.name("inst").unwrap().as_str().to_string(),
-                register: match captured.name("reg") {
-                    Some(s) => match s.as_str() { "a" => 0, "b" => 1, _ => 2},
-                    None => 0,
-                },
-                offset: match captured.name("offset") {
-                    Some(offset_str) => offset_str.as_str().parse::<i32>().unwrap(),
-                    None => 0,
-                },
-            }
-        })
-        .unwrap()
-    }).collect_vec()
+pub fn part_two(input: &str) -> Option<u32> {
+    report(exec(input, 1))
 }
 
-fn exec(codes: Vec<Code>, start: u32) -> u32 {
-    let mut pc: i32 = 0;
-    let range = codes.len() as i32;
+struct Args {
+    isa: Option<&'static dyn InstructionSet>,
+    disassemble: bool,
+    analyze: bool,
+    bench: Option<u32>,
+    trace: bool,
+    breakpoints: Vec<String>,
+    limit: Option<u64>,
+    start: Value,
+}
 
-    let mut reg: HashMap<usize, u32> = HashMap::from([(0, start), (1, 0)]);
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        isa: args.opt_value_from_fn("--isa", |name| isa::by_name(name).ok_or("expected day23, assembunny or extended"))?,
+        disassemble: args.contains("--disassemble"),
+        analyze: args.contains("--analyze"),
+        bench: args.opt_value_from_str("--bench")?,
+        trace: args.contains("--trace"),
+        breakpoints: args.values_from_str("--break")?,
+        limit: args.opt_value_from_str("--limit")?,
+        start: args.opt_value_from_str(["-a", "--start"])?.unwrap_or(0),
+    })
+}
 
-    // debug!(codes);
-    while pc < range {
-        let cmd = &codes[pc as usize];
-        match cmd.inst.as_str() {
-            "hlf" => {
-                if let Some(r) = reg.get_mut(&cmd.register) {
-                    *r /= 2;
-                    pc += 1;
-                };
-            },
-            "tpl" => {
-                if let Some(r) = reg.get_mut(&cmd.register) {
-                    *r *= 3;
-                    pc += 1;
-                };
-            },
-            "inc" => {
-                if let Some(r) = reg.get_mut(&cmd.register) {
-                    *r += 1;
-                    pc += 1;
-                };
-            },
-            "jmp" => {
-                pc += cmd.offset;
+fn registers(machine: &Machine) -> String {
+    machine.program().registers.iter().zip(&machine.registers)
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Runs the program once with `a = start`, printing the trace and the
+/// registers at every breakpoint until it halts or hits the limit.
+fn inspect(program: Program, args: &Args) -> Result<(), VmError> {
+    analysis::analyze(&program.instructions).warnings().iter().for_each(|warning| eprintln!("{warning}"));
+    let breakpoints = args.breakpoints.iter()
+        .map(|text| program.breakpoint(text))
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut machine = Machine::new(program);
+    machine.set_register(Register::A, args.start);
+    machine.set_limit(args.limit);
+    breakpoints.into_iter().for_each(|breakpoint| { machine.add_breakpoint(breakpoint); });
+    if args.trace {
+        machine.enable_trace();
+    }
+    loop {
+        let stop = machine.run()?;
+        machine.take_trace().iter().for_each(|entry| println!("{}", machine.format_trace(entry)));
+        if !machine.output().is_empty() {
+            println!("output: {:?}", machine.output());
+        }
+        match stop {
+            Stop::Breakpoint(idx) => {
+                println!("break `{}` at pc {} after {} steps: {}", args.breakpoints[idx], machine.pc(), machine.steps(), registers(&machine));
             },
-            "jie" => {
-                if let Some(r) = reg.get(&cmd.register) {
-                    if *r % 2 == 0 {
-                        pc += cmd.offset;
-                    } else {
-                        pc += 1;
-                    }
-                };
+            Stop::LimitReached => {
+                println!("limit reached at pc {} after {} steps: {}", machine.pc(), machine.steps(), registers(&machine));
+                return Ok(());
             },
-            "jio" => {
-                if let Some(r) = reg.get(&cmd.register) {
-                    if *r == 1 {
-                        pc += cmd.offset;
-                    } else {
-                        pc += 1;
-                    }
-                };
+            Stop::Halted => {
+                println!("halted after {} steps: {}", machine.steps(), registers(&machine));
+                return Ok(());
             },
-            _ => panic!("{}", format!("unknown instruction: {}", cmd.inst))
         }
     }
-    *reg.get(&1).unwrap()
 }
 
-pub fn part_one(input: &str) -> Option<u32> {
-    let codes = parse_input(input);
-    Some(exec(codes, 0))
+/// Runs `program` from `a = start` `rounds` times, returning the last machine
+/// and the fastest run.
+fn time(program: &Program, start: Value, optimized: bool, rounds: u32) -> Result<(Machine, Duration), VmError> {
+    let mut best = (None, Duration::MAX);
+    for _ in 0..rounds.max(1) {
+        let timer = Instant::now();
+        let mut machine = Machine::new(program.clone());
+        if optimized {
+            machine.set_kernels(optimize::optimize(program));
+        }
+        machine.set_register(Register::A, start);
+        machine.run()?;
+        best = (Some(machine), best.1.min(timer.elapsed()));
+    }
+    Ok((best.0.unwrap(), best.1))
 }
 
-pub fn part_two(input: &str) -> Option<u32> {
-    let codes = parse_input(input);
-    Some(exec(codes, 1))
+/// Interpreted and optimized runs side by side, for both parts.
+fn bench(program: &Program, rounds: u32) -> Result<(), VmError> {
+    let mut covered = 0;
+    for (pc, kernel) in optimize::optimize(program).iter().enumerate() {
+        match kernel {
+            Some(kernel) if pc >= covered => {
+                println!("kernel at {pc}: {kernel:?}");
+                covered = pc + kernel.span();
+            },
+            _ => (),
+        }
+    }
+    println!("{:>5} {:>12} {:>12} {:>12} {:>8}", "a", "steps", "interpreted", "optimized", "speedup");
+    for start in [0, 1] {
+        let (interpreted, slow) = time(program, start, false, rounds)?;
+        let (optimized, fast) = time(program, start, true, rounds)?;
+        if interpreted.registers != optimized.registers || interpreted.steps() != optimized.steps() {
+            eprintln!("optimized run differs for a={start}: {} vs {}", registers(&interpreted), registers(&optimized));
+        }
+        let speedup = slow.as_secs_f64() / fast.as_secs_f64().max(f64::EPSILON);
+        println!("{start:>5} {:>12} {:>12.2?} {:>12.2?} {speedup:>7.1}x", interpreted.steps(), slow, fast);
+    }
+    Ok(())
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 23);
+    let debugging = args.trace || !args.breakpoints.is_empty() || args.limit.is_some();
+    if args.isa.is_some() || args.disassemble || args.analyze || args.bench.is_some() || debugging {
+        let program = match asm::assemble_with(input, args.isa.unwrap_or(&isa::Day23)) {
+            Ok(program) => program,
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        };
+        if args.disassemble {
+            print!("{}", asm::disassemble(&program));
+        } else if args.analyze {
+            print!("{}", analysis::analyze(&program.instructions));
+        } else if let Some(rounds) = args.bench {
+            if let Err(e) = bench(&program, rounds) {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        } else if let Err(e) = inspect(program, &args) {
+            eprintln!("{e}");
+            process::exit(1);
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
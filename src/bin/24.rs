@@ -1,134 +1,128 @@
-// use advent_of_code::debug;
-use std::collections::HashSet;
-use itertools::Itertools;
-
-fn parse(input: &str) -> Vec<u32> {
-    let mut res = input.trim().split("\n")
-        .map(|line| line.parse::<u32>().unwrap())
-        .collect_vec();
-    res.reverse(); res
-    // res
+use advent_of_code::helpers::subset_sum::{partition, SubsetSums};
+use std::process;
+
+fn parse(input: &str) -> Vec<u64> {
+    input.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.trim().parse::<u64>().unwrap())
+        .collect()
 }
 
-fn get_score(group: &Vec<u32>) -> u64 {
-    group.iter().fold(1u64, |acc, e| acc * *e as u64)
+/// Packages split between the compartments, the passenger compartment first.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Loading {
+    compartments: Vec<Vec<u64>>,
 }
 
-type G = Vec<Vec<u32>>;
-fn is_valid(groups: G) -> bool {
-    let mut prev_score = 0u64;
-    for group in groups.iter().filter(|g| g.len() != 0) {
-        let score = get_score(&group);
-        if prev_score > score {
-            return false
-        }
-        prev_score = score;
+impl Loading {
+    /// Product of the passenger compartment's weights, `None` past `u64::MAX`.
+    fn quantum_entanglement(&self) -> Option<u64> {
+        product(&self.compartments[0])
+    }
+
+    /// Whether this is a real split of `weights`: every compartment equally
+    /// heavy, and every package in exactly one of them.
+    fn is_balanced(&self, weights: &[u64]) -> bool {
+        let mut packed = self.compartments.concat();
+        let mut expected = weights.to_vec();
+        packed.sort();
+        expected.sort();
+        let share = self.compartments[0].iter().sum::<u64>();
+        packed == expected && self.compartments.iter().all(|compartment| compartment.iter().sum::<u64>() == share)
     }
-    true
 }
 
-fn grouping(nums: &Vec<u32>, group_amnt: u32) -> Vec<G> {
-    fn helper(
-        nums: &Vec<u32>,
-        target: u32,
-        group_idx: usize,
-        groups: &mut G,
-        target_sum: u32,
-        target_idx: usize,
-        result: &mut Vec<G>,
-        found: &mut HashSet<Vec<u32>>
-    ) {
-        // debug!(groups);
-        if group_idx > 0 && found.contains(&groups[0]) { return; }
-
-        // magic number here, to decrease amount of candidate at first n group
-        if group_idx < target_idx && groups[group_idx].len() > 6 { return }
-
-        if group_idx == target_idx {
-            groups[group_idx] = nums.clone();
-            if is_valid(groups.clone()) {
-                // debug!(groups);
-                result.push(groups.clone());
-                found.insert(groups[0].clone());
-            }
-            return;
-        }
+fn product(weights: &[u64]) -> Option<u64> {
+    weights.iter().try_fold(1u64, |product, &weight| product.checked_mul(weight))
+}
 
-        for i in 0..nums.len() {
-            let mut new_nums = nums.clone();
-            let popped = new_nums.remove(i);
-            if groups[group_idx].len() > 0 && popped > *groups[group_idx].last().unwrap() { continue; }
-            if popped < target {
-                groups[group_idx].push(popped);
-                helper(&new_nums, target - popped,
-                    group_idx, groups,
-                    target_sum, target_idx, result, found);
-                groups[group_idx].pop();
-            } else if popped == target {
-                groups[group_idx].push(popped);
-                if (
-                    group_idx > 0
-                    && !found.contains(&groups[0])
-                    && get_score(&groups[group_idx-1]) < get_score(&groups[group_idx])
-                 ) || (
-                     group_idx == 0
-                     && !found.contains(&groups[0])
-                    && groups[0].len() <= 6
-                ) {
-                    helper(&new_nums, target_sum,
-                        group_idx + 1, groups,
-                        target_sum, target_idx, result, found);
-                }
-                groups[group_idx].pop();
+/// The loading with the fewest packages up front, then the smallest quantum
+/// entanglement there, among those where the other packages split evenly.
+fn balance(weights: &[u64], compartments: usize) -> Option<Loading> {
+    let total = weights.iter().sum::<u64>();
+    if compartments == 0 || total % compartments as u64 != 0 {
+        return None;
+    }
+    let sums = SubsetSums::new(weights, total / compartments as u64).map_err(|e| eprintln!("{e}")).ok()?;
+    for size in 1..=weights.len() {
+        // every front of this size is held at once to try them in order of
+        // quantum entanglement, overflowing ones last; the smallest sizes
+        // that balance only have a few of them
+        let mut fronts = sums.of_size(size)
+            .map(|front| {
+                let entanglement = product(&front.iter().map(|&idx| weights[idx]).collect::<Vec<_>>());
+                (entanglement.is_none(), entanglement, front)
+            })
+            .collect::<Vec<_>>();
+        fronts.sort();
+        for (_, _, front) in fronts {
+            let rest = (0..weights.len()).filter(|idx| !front.contains(idx)).map(|idx| weights[idx]).collect::<Vec<_>>();
+            if let Some(split) = partition(&rest, compartments - 1) {
+                let mut loading = vec![front.iter().map(|&idx| weights[idx]).collect::<Vec<_>>()];
+                loading.extend(split.iter().map(|group| group.iter().map(|&idx| rest[idx]).collect()));
+                return Some(Loading { compartments: loading });
             }
         }
     }
-    let total_sum = nums.iter().sum::<u32>();
-    let target_sum = total_sum / group_amnt;
-    // let mut found: HashSet<Vec<u32>> = HashSet::new();
-    let mut result: Vec<G> = vec![];
-
-    let mut start_group = vec![];
-    for _ in 0..group_amnt {
-        start_group.push(vec![]);
-    }
-
-    helper(
-        nums, target_sum,
-        0, &mut start_group,
-        target_sum, (group_amnt - 1) as usize, &mut result, &mut HashSet::new());
-    result
+    None
 }
 
-
-fn find_min(grouped: Vec<G>) -> u64 {
-    let mut min_len = usize::MAX;
-    for group in &grouped {
-        if group[0].len() < min_len { min_len = group[0].len(); }
-    }
-
-    let mut min: u64 = u64::MAX;
-    for group in grouped.iter().filter(|gs| gs[0].len() == min_len) {
-        let prod: u64 = group[0].iter().fold(1u64, |acc, e| acc * *e as u64);
-        if prod < min { min = prod; }
-    }
-    min
+fn entanglement(weights: &[u64], compartments: usize) -> Option<u64> {
+    balance(weights, compartments)?.quantum_entanglement().or_else(|| {
+        eprintln!("The quantum entanglement does not fit in 64 bits.");
+        None
+    })
 }
 
 pub fn part_one(input: &str) -> Option<u64> {
-    let data = parse(input);
-    let res = grouping(&data, 3);
-    Some(find_min(res))
+    entanglement(&parse(input), 3)
 }
 
 pub fn part_two(input: &str) -> Option<u64> {
-    let data = parse(input);
-    let res = grouping(&data, 4);
-    Some(find_min(res))
+    entanglement(&parse(input), 4)
+}
+
+struct Args {
+    compartments: Option<usize>,
+}
+
+fn parse_args() -> Result<Args, pico_args::Error> {
+    let mut args = pico_args::Arguments::from_env();
+    Ok(Args {
+        compartments: args.opt_value_from_str("--compartments")?,
+    })
 }
 
 fn main() {
+    let args = match parse_args() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("Failed to process arguments: {e}");
+            process::exit(1);
+        }
+    };
+
     let input = &advent_of_code::read_file("inputs", 24);
+    if let Some(compartments) = args.compartments {
+        let Some(loading) = balance(&parse(input), compartments) else {
+            eprintln!("The packages cannot be split into {compartments} equal compartments.");
+            process::exit(1);
+        };
+        if !loading.is_balanced(&parse(input)) {
+            eprintln!("The loading found does not balance the sleigh.");
+            process::exit(1);
+        }
+        match loading.quantum_entanglement() {
+            Some(entanglement) => println!("🎄 Quantum entanglement: {entanglement}"),
+            None => println!("🎄 Quantum entanglement: over {}", u64::MAX),
+        }
+        for (idx, compartment) in loading.compartments.iter().enumerate() {
+            let label = if idx == 0 { "passenger".to_string() } else { format!("compartment {}", idx + 1) };
+            let packages = compartment.iter().map(|weight| weight.to_string()).collect::<Vec<_>>();
+            println!("    {label}: {} (weight {})", packages.join(" "), compartment.iter().sum::<u64>());
+        }
+        return;
+    }
     advent_of_code::solve!(1, part_one, input);
     advent_of_code::solve!(2, part_two, input);
 }
@@ -148,4 +142,23 @@ mod tests {
         let input = advent_of_code::read_file("examples", 24);
         assert_eq!(part_two(&input).unwrap(), 44);
     }
+
+    #[test]
+    fn test_loading() {
+        let weights = parse(&advent_of_code::read_file("examples", 24));
+        let loading = balance(&weights, 3).unwrap();
+        assert_eq!(loading.compartments[0], [9, 11]);
+        assert!(loading.is_balanced(&weights));
+        assert!(balance(&weights, 5).is_some_and(|loading| loading.is_balanced(&weights)));
+        assert_eq!(balance(&weights, 7), None);
+        // 2 + 10 + 21 is less entangled, but leaves 3, 5, 7, 14, 17, 20, which do not split in two
+        let weights = [2, 3, 5, 7, 10, 14, 17, 20, 21];
+        let loading = balance(&weights, 3).unwrap();
+        assert_eq!((loading.compartments[0].as_slice(), loading.quantum_entanglement()), (&[2, 14, 17][..], Some(476)));
+        assert!(loading.is_balanced(&weights));
+        // 9000^5 is past u64::MAX, so the loading has no number to report
+        let weights = [9000; 10];
+        assert_eq!(balance(&weights, 2).map(|loading| loading.compartments[0].len()), Some(5));
+        assert_eq!(entanglement(&weights, 2), None);
+    }
 }
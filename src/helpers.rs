@@ -3,9 +3,26 @@
  * Example import from this file: `use advent_of_code::helpers::example_fn;`.
  */
 
+pub mod circuit;
+pub mod graph;
+pub mod image;
+pub mod json;
+pub mod look_and_say;
+pub mod molecule;
+pub mod password;
+pub mod presents;
+pub mod race;
+pub mod recipe;
+pub mod records;
+pub mod rpg;
+pub mod subset_sum;
+pub mod tsp;
+pub mod vm;
+pub mod wizard;
+
 #[macro_export]
 macro_rules! debug {
     ($var:expr) => {
         println!("{:?} = {:?}", stringify!($var), $var);
     };
-}
\ No newline at end of file
+}
@@ -0,0 +1,571 @@
+/*
+ * Wire circuit simulator for day 7.
+ * Example: `let mut circuit = Circuit::parse(input)?; circuit.signal("a")?;`
+ */
+use lazy_static::lazy_static;
+use petgraph::algo;
+use petgraph::dot::{Config, Dot};
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::NodeFiltered;
+use petgraph::Direction;
+use regex::Regex;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+pub type WireId = usize;
+
+/// One node per wire (`NodeIndex::new(id)` is wire `id`), with an edge from
+/// every gate input to the wire it drives.
+pub type CircuitGraph = DiGraph<String, ()>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Wire(WireId),
+    Value(u16),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gate {
+    Assign(Operand),
+    Not(Operand),
+    And(Operand, Operand),
+    Or(Operand, Operand),
+    LShift(Operand, Operand),
+    RShift(Operand, Operand),
+}
+
+impl Gate {
+    pub fn operands(&self) -> Vec<Operand> {
+        match *self {
+            Gate::Assign(src) | Gate::Not(src) => vec![src],
+            Gate::And(l, r) | Gate::Or(l, r) | Gate::LShift(l, r) | Gate::RShift(l, r) => vec![l, r],
+        }
+    }
+
+    pub fn inputs(&self) -> impl Iterator<Item = WireId> {
+        self.operands().into_iter().filter_map(|operand| match operand {
+            Operand::Wire(id) => Some(id),
+            Operand::Value(_) => None,
+        })
+    }
+
+    /// Applies the gate, resolving each operand through `value_of`.
+    pub fn apply(&self, mut value_of: impl FnMut(Operand) -> u16) -> u16 {
+        match *self {
+            Gate::Assign(src) => value_of(src),
+            Gate::Not(src) => !value_of(src),
+            Gate::And(l, r) => value_of(l) & value_of(r),
+            Gate::Or(l, r) => value_of(l) | value_of(r),
+            Gate::LShift(l, r) => value_of(l).checked_shl(value_of(r) as u32).unwrap_or(0),
+            Gate::RShift(l, r) => value_of(l).checked_shr(value_of(r) as u32).unwrap_or(0),
+        }
+    }
+
+    /// Replaces every operand `known` can resolve by its value, and collapses
+    /// the gate into a constant once nothing else is left.
+    pub fn fold(&self, known: impl Fn(Operand) -> Option<u16>) -> Gate {
+        let substitute = |operand: Operand| known(operand).map_or(operand, Operand::Value);
+        let gate = match *self {
+            Gate::Assign(src) => Gate::Assign(substitute(src)),
+            Gate::Not(src) => Gate::Not(substitute(src)),
+            Gate::And(l, r) => Gate::And(substitute(l), substitute(r)),
+            Gate::Or(l, r) => Gate::Or(substitute(l), substitute(r)),
+            Gate::LShift(l, r) => Gate::LShift(substitute(l), substitute(r)),
+            Gate::RShift(l, r) => Gate::RShift(substitute(l), substitute(r)),
+        };
+        if gate.inputs().next().is_none() {
+            Gate::Assign(Operand::Value(gate.apply(|operand| match operand {
+                Operand::Value(value) => value,
+                Operand::Wire(_) => unreachable!(),
+            })))
+        } else {
+            gate
+        }
+    }
+
+    pub fn constant(&self) -> Option<u16> {
+        match *self {
+            Gate::Assign(Operand::Value(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CircuitError {
+    Parse { line: usize, text: String },
+    DuplicateWire(String),
+    UnknownWire(String),
+    Undriven(String),
+    Cycle(Vec<String>),
+}
+
+impl Display for CircuitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CircuitError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            CircuitError::DuplicateWire(name) => write!(f, "wire `{name}` is driven more than once"),
+            CircuitError::UnknownWire(name) => write!(f, "wire `{name}` does not exist"),
+            CircuitError::Undriven(name) => write!(f, "wire `{name}` has no signal source"),
+            CircuitError::Cycle(path) => write!(f, "circuit has a cycle: {}", path.join(" -> ")),
+        }
+    }
+}
+
+lazy_static! {
+    static ref STMT_REGEX: Regex = Regex::new(
+        r"^(?:(?<l>[a-z]+|\d+) )?(?:(?<ops>[A-Z]+) )?(?<r>[a-z]+|\d+) -> (?<tgt>[a-z]+)$"
+    ).unwrap();
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Circuit {
+    names: Vec<String>,
+    index: HashMap<String, WireId>,
+    gates: Vec<Option<Gate>>,
+    dependents: Vec<Vec<WireId>>,
+    overrides: Vec<Option<u16>>,
+    cache: Vec<Option<u16>>,
+}
+
+impl Circuit {
+    pub fn parse(input: &str) -> Result<Circuit, CircuitError> {
+        let mut circuit = Circuit::default();
+        for (idx, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let parse_error = || CircuitError::Parse { line: idx + 1, text: line.to_string() };
+            let captured = STMT_REGEX.captures(line.trim()).ok_or_else(parse_error)?;
+            let mut operand = |name: &str| match name.parse::<u16>() {
+                Ok(value) => Ok(Operand::Value(value)),
+                Err(_) if name.chars().all(|c| c.is_ascii_lowercase()) => Ok(Operand::Wire(circuit.intern(name))),
+                Err(_) => Err(parse_error()),
+            };
+            let src_l = captured.name("l").map(|l| operand(l.as_str())).transpose()?;
+            let src_r = operand(&captured["r"])?;
+            let gate = match (src_l, captured.name("ops").map(|ops| ops.as_str())) {
+                (None, None) => Gate::Assign(src_r),
+                (None, Some("NOT")) => Gate::Not(src_r),
+                (Some(l), Some("AND")) => Gate::And(l, src_r),
+                (Some(l), Some("OR")) => Gate::Or(l, src_r),
+                (Some(l), Some("LSHIFT")) => Gate::LShift(l, src_r),
+                (Some(l), Some("RSHIFT")) => Gate::RShift(l, src_r),
+                _ => return Err(parse_error()),
+            };
+            let target = circuit.intern(&captured["tgt"]);
+            if circuit.gates[target].is_some() {
+                return Err(CircuitError::DuplicateWire(captured["tgt"].to_string()));
+            }
+            circuit.connect(target, gate);
+        }
+        Ok(circuit)
+    }
+
+    fn intern(&mut self, name: &str) -> WireId {
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.index.insert(name.to_string(), id);
+        self.gates.push(None);
+        self.dependents.push(vec![]);
+        self.overrides.push(None);
+        self.cache.push(None);
+        id
+    }
+
+    fn connect(&mut self, target: WireId, gate: Gate) {
+        for source in gate.inputs() {
+            self.dependents[source].push(target);
+        }
+        self.gates[target] = Some(gate);
+        self.invalidate(target);
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn wire(&self, name: &str) -> Result<WireId, CircuitError> {
+        self.index.get(name).copied().ok_or_else(|| CircuitError::UnknownWire(name.to_string()))
+    }
+
+    pub fn name(&self, id: WireId) -> &str {
+        &self.names[id]
+    }
+
+    pub fn wires(&self) -> impl Iterator<Item = (WireId, &str)> {
+        self.names.iter().map(String::as_str).enumerate()
+    }
+
+    pub fn gate(&self, id: WireId) -> Option<&Gate> {
+        self.gates[id].as_ref()
+    }
+
+    pub fn dependents(&self, id: WireId) -> &[WireId] {
+        &self.dependents[id]
+    }
+
+    pub fn override_of(&self, id: WireId) -> Option<u16> {
+        self.overrides[id]
+    }
+
+    pub fn operand_name(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Wire(id) => self.names[id].clone(),
+            Operand::Value(value) => value.to_string(),
+        }
+    }
+
+    /// The gate driving `id` in puzzle syntax, e.g. `x AND y`.
+    pub fn expression(&self, id: WireId) -> Option<String> {
+        let name = |operand| self.operand_name(operand);
+        Some(match self.gates[id]? {
+            Gate::Assign(src) => name(src),
+            Gate::Not(src) => format!("NOT {}", name(src)),
+            Gate::And(l, r) => format!("{} AND {}", name(l), name(r)),
+            Gate::Or(l, r) => format!("{} OR {}", name(l), name(r)),
+            Gate::LShift(l, r) => format!("{} LSHIFT {}", name(l), name(r)),
+            Gate::RShift(l, r) => format!("{} RSHIFT {}", name(l), name(r)),
+        })
+    }
+
+    /// The full statement for `id`, e.g. `x AND y -> d`.
+    pub fn statement(&self, id: WireId) -> Option<String> {
+        Some(format!("{} -> {}", self.expression(id)?, self.names[id]))
+    }
+
+    pub fn to_graph(&self) -> CircuitGraph {
+        let mut graph = CircuitGraph::with_capacity(self.len(), self.len() * 2);
+        for name in &self.names {
+            graph.add_node(name.clone());
+        }
+        for (target, gate) in self.gates.iter().enumerate() {
+            for source in gate.iter().flat_map(Gate::inputs) {
+                graph.add_edge(NodeIndex::new(source), NodeIndex::new(target), ());
+            }
+        }
+        graph
+    }
+
+    /// Wires ordered so every gate comes after its inputs.
+    pub fn topological_order(&self) -> Result<Vec<WireId>, CircuitError> {
+        self.topological_order_of(|_| true)
+    }
+
+    fn topological_order_of(&self, keep: impl Fn(WireId) -> bool) -> Result<Vec<WireId>, CircuitError> {
+        let graph = self.to_graph();
+        let filtered = NodeFiltered::from_fn(&graph, |node: NodeIndex| keep(node.index()));
+        match algo::toposort(&filtered, None) {
+            Ok(order) => Ok(order.into_iter().map(NodeIndex::index).collect()),
+            Err(cycle) => Err(self.cycle_through(&graph, cycle.node_id())),
+        }
+    }
+
+    /// Walks gate inputs from `start` until it comes back, shortest loop first.
+    fn cycle_through(&self, graph: &CircuitGraph, start: NodeIndex) -> CircuitError {
+        let mut previous: HashMap<NodeIndex, NodeIndex> = HashMap::new();
+        let mut queue = VecDeque::from([start]);
+        while let Some(current) = queue.pop_front() {
+            for source in graph.neighbors_directed(current, Direction::Incoming) {
+                if source == start {
+                    let mut path = vec![current];
+                    while let Some(&prev) = previous.get(path.last().unwrap()) {
+                        path.push(prev);
+                    }
+                    path.reverse();
+                    path.push(start);
+                    return CircuitError::Cycle(path.iter().map(|&id| graph[id].clone()).collect());
+                }
+                if let Entry::Vacant(entry) = previous.entry(source) {
+                    entry.insert(current);
+                    queue.push_back(source);
+                }
+            }
+        }
+        CircuitError::Cycle(vec![graph[start].clone()])
+    }
+
+    /// Graphviz rendering, one box per wire labelled with its statement.
+    pub fn to_dot(&self) -> String {
+        let graph = self.to_graph().map(
+            |id, name| self.statement(id.index()).unwrap_or_else(|| name.clone()),
+            |_, _| "",
+        );
+        format!("{}", Dot::with_config(&graph, &[Config::EdgeNoLabel]))
+    }
+
+    /// Every wire `id` needs a signal from, `id` included.
+    fn upstream(&self, id: WireId, gates: &[Option<Gate>]) -> Vec<bool> {
+        let mut needed = vec![false; self.len()];
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if !needed[current] {
+                needed[current] = true;
+                stack.extend(gates[current].iter().flat_map(Gate::inputs));
+            }
+        }
+        needed
+    }
+
+    /// A circuit computing the same `output`, with constants folded through
+    /// every gate and the wires `output` does not need removed. Overrides are
+    /// folded in as constants; undriven wires stay as free inputs.
+    pub fn simplify(&self, output: &str) -> Result<Circuit, CircuitError> {
+        let output = self.wire(output)?;
+        let gates = self.gates.iter().zip(&self.overrides)
+            .map(|(gate, value)| value.map(|value| Gate::Assign(Operand::Value(value))).or(*gate))
+            .collect::<Vec<_>>();
+        let live = self.upstream(output, &gates);
+
+        let mut folded: Vec<Option<Gate>> = vec![None; self.len()];
+        let order = self.topological_order_of(|id| live[id])?;
+        for &id in &order {
+            folded[id] = gates[id].map(|gate| gate.fold(|operand| match operand {
+                Operand::Value(value) => Some(value),
+                Operand::Wire(source) => folded[source].and_then(|gate| gate.constant()),
+            }));
+        }
+
+        let kept = self.upstream(output, &folded);
+        let mut simplified = Circuit::default();
+        for &id in order.iter().filter(|&&id| kept[id]) {
+            let target = simplified.intern(&self.names[id]);
+            if let Some(gate) = folded[id] {
+                let mut operand = |operand| match operand {
+                    Operand::Wire(source) => Operand::Wire(simplified.intern(&self.names[source])),
+                    value => value,
+                };
+                let gate = match gate {
+                    Gate::Assign(src) => Gate::Assign(operand(src)),
+                    Gate::Not(src) => Gate::Not(operand(src)),
+                    Gate::And(l, r) => Gate::And(operand(l), operand(r)),
+                    Gate::Or(l, r) => Gate::Or(operand(l), operand(r)),
+                    Gate::LShift(l, r) => Gate::LShift(operand(l), operand(r)),
+                    Gate::RShift(l, r) => Gate::RShift(operand(l), operand(r)),
+                };
+                simplified.connect(target, gate);
+            }
+        }
+        Ok(simplified)
+    }
+
+    /// The circuit back in puzzle syntax, one statement per driven wire,
+    /// inputs before the gates that read them.
+    pub fn to_netlist(&self) -> String {
+        let order = self.topological_order().unwrap_or_else(|_| (0..self.len()).collect());
+        order.into_iter()
+            .filter_map(|id| self.statement(id))
+            .map(|stmt| stmt + "\n")
+            .collect()
+    }
+
+    /// Forces `name` to carry `value` regardless of its gate. Only wires that
+    /// depend on `name` are recomputed on the next query.
+    pub fn set_override(&mut self, name: &str, value: u16) -> Result<(), CircuitError> {
+        let id = self.wire(name)?;
+        if self.overrides[id] != Some(value) {
+            self.overrides[id] = Some(value);
+            self.invalidate(id);
+        }
+        Ok(())
+    }
+
+    pub fn clear_override(&mut self, name: &str) -> Result<(), CircuitError> {
+        let id = self.wire(name)?;
+        if self.overrides[id].take().is_some() {
+            self.invalidate(id);
+        }
+        Ok(())
+    }
+
+    /// Drops the cached signal of `id` and everything downstream of it.
+    fn invalidate(&mut self, id: WireId) {
+        let mut stack = vec![id];
+        while let Some(current) = stack.pop() {
+            if self.cache[current].take().is_some() {
+                stack.extend(self.dependents[current].iter().copied());
+            }
+        }
+    }
+
+    pub fn is_cached(&self, id: WireId) -> bool {
+        self.cache[id].is_some()
+    }
+
+    pub fn signal(&mut self, name: &str) -> Result<u16, CircuitError> {
+        let id = self.wire(name)?;
+        self.signal_of(id)
+    }
+
+    /// Evaluates `id`, computing only the wires without a cached signal.
+    pub fn signal_of(&mut self, id: WireId) -> Result<u16, CircuitError> {
+        // each entry is a wire and the index of its next input to visit,
+        // so the stack is always the path from `id` down to the current wire.
+        let mut path: Vec<(WireId, usize)> = vec![(id, 0)];
+        while let Some(&(current, next)) = path.last() {
+            if self.is_resolved(current) {
+                path.pop();
+                continue;
+            }
+            let gate = self.gates[current].ok_or_else(|| CircuitError::Undriven(self.names[current].clone()))?;
+            match gate.inputs().nth(next) {
+                Some(source) => {
+                    path.last_mut().unwrap().1 += 1;
+                    if self.is_resolved(source) {
+                        continue;
+                    }
+                    if let Some(start) = path.iter().position(|&(wire, _)| wire == source) {
+                        let mut cycle = path[start..].iter().map(|&(wire, _)| self.names[wire].clone()).collect::<Vec<_>>();
+                        cycle.push(self.names[source].clone());
+                        return Err(CircuitError::Cycle(cycle));
+                    }
+                    path.push((source, 0));
+                }
+                None => {
+                    self.cache[current] = Some(gate.apply(|operand| match operand {
+                        Operand::Value(value) => value,
+                        Operand::Wire(source) => self.cache[source].unwrap(),
+                    }));
+                    path.pop();
+                }
+            }
+        }
+        Ok(self.cache[id].unwrap())
+    }
+
+    /// Fills the cache from an override if there is one.
+    fn is_resolved(&mut self, id: WireId) -> bool {
+        if self.cache[id].is_none() {
+            self.cache[id] = self.overrides[id];
+        }
+        self.cache[id].is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let circuit = Circuit::parse("123 -> x\nNOT x -> yy\nx AND 1 -> z\n1 LSHIFT yy -> abc").unwrap();
+        let (x, yy, z, abc) = (
+            circuit.wire("x").unwrap(), circuit.wire("yy").unwrap(),
+            circuit.wire("z").unwrap(), circuit.wire("abc").unwrap()
+        );
+        assert_eq!(circuit.gate(x), Some(&Gate::Assign(Operand::Value(123))));
+        assert_eq!(circuit.gate(yy), Some(&Gate::Not(Operand::Wire(x))));
+        assert_eq!(circuit.gate(z), Some(&Gate::And(Operand::Wire(x), Operand::Value(1))));
+        assert_eq!(circuit.gate(abc), Some(&Gate::LShift(Operand::Value(1), Operand::Wire(yy))));
+
+        assert_eq!(
+            Circuit::parse("1 -> a\nx FOO y -> z").unwrap_err(),
+            CircuitError::Parse { line: 2, text: "x FOO y -> z".to_string() }
+        );
+        assert_eq!(Circuit::parse("1 -> a\n2 -> a").unwrap_err(), CircuitError::DuplicateWire("a".to_string()));
+    }
+
+    #[test]
+    fn test_signal() {
+        let mut circuit = Circuit::parse(&advent_of_code_example()).unwrap();
+        assert_eq!(circuit.signal("d"), Ok(72));
+        assert_eq!(circuit.signal("e"), Ok(507));
+        assert_eq!(circuit.signal("f"), Ok(492));
+        assert_eq!(circuit.signal("g"), Ok(114));
+        assert_eq!(circuit.signal("h"), Ok(65412));
+        assert_eq!(circuit.signal("i"), Ok(65079));
+        assert_eq!(circuit.signal("nope"), Err(CircuitError::UnknownWire("nope".to_string())));
+    }
+
+    #[test]
+    fn test_override_is_incremental() {
+        let mut circuit = Circuit::parse(&advent_of_code_example()).unwrap();
+        for id in 0..circuit.len() {
+            circuit.signal_of(id).unwrap();
+        }
+        circuit.set_override("y", 0).unwrap();
+        let cached = |circuit: &Circuit, name| circuit.is_cached(circuit.wire(name).unwrap());
+        assert!(cached(&circuit, "x") && cached(&circuit, "f") && cached(&circuit, "h"));
+        assert!(!cached(&circuit, "d") && !cached(&circuit, "e") && !cached(&circuit, "i"));
+        assert_eq!(circuit.signal("e"), Ok(123));
+
+        circuit.clear_override("y").unwrap();
+        assert_eq!(circuit.signal("e"), Ok(507));
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut circuit = Circuit::parse("a -> b\nb AND c -> d\nd -> a\n1 -> c\nq -> r").unwrap();
+        assert_eq!(
+            circuit.signal("d"),
+            Err(CircuitError::Cycle(vec!["d".into(), "b".into(), "a".into(), "d".into()]))
+        );
+        assert_eq!(circuit.signal("r"), Err(CircuitError::Undriven("q".to_string())));
+
+        circuit.set_override("a", 7).unwrap();
+        assert_eq!(circuit.signal("d"), Ok(1));
+    }
+
+    #[test]
+    fn test_graph() {
+        let circuit = Circuit::parse(&advent_of_code_example()).unwrap();
+        let graph = circuit.to_graph();
+        assert_eq!(graph.node_count(), circuit.len());
+        assert_eq!(graph.edge_count(), 9);
+
+        let order = circuit.topological_order().unwrap();
+        let position = |name| order.iter().position(|&id| id == circuit.wire(name).unwrap()).unwrap();
+        assert!(position("x") < position("d") && position("y") < position("d"));
+        assert!(position("x") < position("a"));
+
+        assert_eq!(circuit.statement(circuit.wire("f").unwrap()), Some("x LSHIFT 2 -> f".to_string()));
+        let dot = circuit.to_dot();
+        assert!(dot.starts_with("digraph {"));
+        assert!(dot.contains("label = \"NOT y -> i\""));
+
+        let circuit = Circuit::parse("a -> b\nb AND c -> d\nd -> a\n1 -> c").unwrap();
+        match circuit.topological_order() {
+            Err(CircuitError::Cycle(path)) => {
+                assert_eq!(path.len(), 4);
+                assert_eq!(path.first(), path.last());
+            },
+            other => panic!("expected a cycle, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_simplify() {
+        let input = advent_of_code_example() + "\nd OR q -> k\nk AND 7 -> z\nNOT in -> n\nn RSHIFT g -> out\ne -> q";
+        let mut circuit = Circuit::parse(&input).unwrap();
+
+        let mut simplified = circuit.simplify("z").unwrap();
+        assert_eq!(simplified.to_netlist(), "3 -> z\n");
+        assert_eq!(simplified.signal("z"), circuit.signal("z"));
+
+        // `in` is never driven, so only the gates touching it survive.
+        let mut simplified = circuit.simplify("out").unwrap();
+        assert_eq!(simplified.to_netlist(), "NOT in -> n\nn RSHIFT 114 -> out\n");
+        assert!(simplified.wire("x").is_err());
+        for value in [0, 1, 1234, u16::MAX] {
+            circuit.set_override("in", value).unwrap();
+            simplified.set_override("in", value).unwrap();
+            assert_eq!(simplified.signal("out"), circuit.signal("out"));
+        }
+
+        for id in 0..circuit.len() {
+            let name = circuit.name(id).to_string();
+            if let Ok(expected) = circuit.signal_of(id) {
+                assert_eq!(circuit.simplify(&name).unwrap().signal(&name), Ok(expected));
+            }
+        }
+    }
+
+    fn advent_of_code_example() -> String {
+        crate::read_file("examples", 7)
+    }
+}
@@ -0,0 +1,175 @@
+/*
+ * Weighted graphs read from "A to B = 5" style puzzle lines, with node names
+ * interned to indices so solvers can work on plain integers.
+ * Example: `WeightedGraph::parse_with(input, false, parse_edge)?.distance_matrix(None)`.
+ */
+use crate::helpers::tsp::{Cost, DistanceMatrix};
+use petgraph::graph::{DiGraph, NodeIndex, UnGraph};
+use std::collections::HashMap;
+use std::fmt::Display;
+
+pub type NodeId = usize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    Parse { line: usize, text: String },
+}
+
+impl Display for GraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GraphError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WeightedGraph {
+    directed: bool,
+    names: Vec<String>,
+    index: HashMap<String, NodeId>,
+    edges: Vec<(NodeId, NodeId, Cost)>,
+}
+
+impl WeightedGraph {
+    pub fn new(directed: bool) -> WeightedGraph {
+        WeightedGraph { directed, ..WeightedGraph::default() }
+    }
+
+    /// One edge per non-blank line, as `(from, to, weight)` from `parse_line`.
+    pub fn parse_with<'a>(
+        input: &'a str,
+        directed: bool,
+        parse_line: impl Fn(&'a str) -> Option<(&'a str, &'a str, Cost)>,
+    ) -> Result<WeightedGraph, GraphError> {
+        let mut graph = WeightedGraph::new(directed);
+        for (idx, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let (from, to, weight) = parse_line(line.trim())
+                .ok_or_else(|| GraphError::Parse { line: idx + 1, text: line.to_string() })?;
+            graph.add_edge(from, to, weight);
+        }
+        Ok(graph)
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    /// Index of `name`, adding it as a new node the first time it is seen.
+    pub fn add_node(&mut self, name: &str) -> NodeId {
+        if let Some(&id) = self.index.get(name) {
+            return id;
+        }
+        self.names.push(name.to_string());
+        self.index.insert(name.to_string(), self.names.len() - 1);
+        self.names.len() - 1
+    }
+
+    pub fn add_edge(&mut self, from: &str, to: &str, weight: Cost) {
+        let (from, to) = (self.add_node(from), self.add_node(to));
+        self.edges.push((from, to, weight));
+    }
+
+    pub fn node(&self, name: &str) -> Option<NodeId> {
+        self.index.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.names[id]
+    }
+
+    /// Node names, indexed by `NodeId`.
+    pub fn names(&self) -> &[String] {
+        &self.names
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn edges(&self) -> &[(NodeId, NodeId, Cost)] {
+        &self.edges
+    }
+
+    /// Edge weights as a matrix; undirected edges fill both directions and pairs
+    /// without an edge get `missing`. A repeated edge keeps its last weight.
+    pub fn distance_matrix(&self, missing: Option<Cost>) -> DistanceMatrix {
+        let mut matrix = DistanceMatrix::new(self.node_count());
+        if let Some(cost) = missing {
+            for a in 0..self.node_count() {
+                for b in (0..self.node_count()).filter(|&b| b != a) {
+                    matrix.set(a, b, cost);
+                }
+            }
+        }
+        for &(from, to, weight) in &self.edges {
+            match self.directed {
+                true => matrix.set(from, to, weight),
+                false => matrix.set_both(from, to, weight),
+            }
+        }
+        matrix
+    }
+
+    /// Directed petgraph view; node `i` is `NodeIndex::new(i)`. Undirected
+    /// edges become a pair of opposite edges.
+    pub fn to_digraph(&self) -> DiGraph<String, Cost> {
+        let mut graph = DiGraph::new();
+        self.names.iter().for_each(|name| { graph.add_node(name.clone()); });
+        for &(from, to, weight) in &self.edges {
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), weight);
+            if !self.directed {
+                graph.add_edge(NodeIndex::new(to), NodeIndex::new(from), weight);
+            }
+        }
+        graph
+    }
+
+    /// Undirected petgraph view; directed edges lose their direction.
+    pub fn to_ungraph(&self) -> UnGraph<String, Cost> {
+        let mut graph = UnGraph::new_undirected();
+        self.names.iter().for_each(|name| { graph.add_node(name.clone()); });
+        for &(from, to, weight) in &self.edges {
+            graph.add_edge(NodeIndex::new(from), NodeIndex::new(to), weight);
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_line(line: &str) -> Option<(&str, &str, Cost)> {
+        let (names, weight) = line.split_once(" = ")?;
+        let (from, to) = names.split_once(" to ")?;
+        Some((from, to, weight.parse().ok()?))
+    }
+
+    #[test]
+    fn test_parse() {
+        let input = "London to Dublin = 464\nLondon to Belfast = 518\n\nDublin to Belfast = 141\n";
+        let mut graph = WeightedGraph::parse_with(input, false, parse_line).unwrap();
+        assert_eq!(graph.names(), ["London", "Dublin", "Belfast"]);
+        assert_eq!(graph.node("Belfast"), Some(2));
+        let matrix = graph.distance_matrix(None);
+        assert_eq!((matrix.get(2, 1), matrix.get(0, 0)), (Some(141), None));
+        assert_eq!(graph.to_digraph().edge_count(), 6);
+        assert_eq!(graph.to_ungraph().edge_count(), 3);
+
+        let you = graph.add_node("you");
+        assert_eq!((you, graph.add_node("you")), (3, 3));
+        assert_eq!(graph.distance_matrix(Some(0)).get(you, 0), Some(0));
+
+        let error = WeightedGraph::parse_with("a to b = 1\nnonsense", true, parse_line).unwrap_err();
+        assert_eq!(error.to_string(), "line 2: cannot parse `nonsense`");
+    }
+
+    #[test]
+    fn test_directed() {
+        let graph = WeightedGraph::parse_with("a to b = 3\nb to a = -1", true, parse_line).unwrap();
+        let matrix = graph.distance_matrix(None);
+        assert_eq!((matrix.get(0, 1), matrix.get(1, 0)), (Some(3), Some(-1)));
+        assert_eq!(graph.to_digraph().edge_count(), 2);
+    }
+}
@@ -0,0 +1,238 @@
+/*
+ * Render light grids (day 6, day 18) to PBM / PGM / PNG images.
+ * Example: `image::write_image("out/final.png", &map, ImageFormat::Png)`.
+ */
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Pbm,
+    Pgm,
+    Png,
+}
+
+impl ImageFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ImageFormat::Pbm => "pbm",
+            ImageFormat::Pgm => "pgm",
+            ImageFormat::Png => "png",
+        }
+    }
+}
+
+impl FromStr for ImageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "pbm" => Ok(ImageFormat::Pbm),
+            "pgm" => Ok(ImageFormat::Pgm),
+            "png" => Ok(ImageFormat::Png),
+            other => Err(format!("unknown image format `{other}` (expected pbm, pgm or png)")),
+        }
+    }
+}
+
+/// A rectangular grid of light levels, `0` being off.
+pub trait Raster {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    fn level(&self, x: usize, y: usize) -> u32;
+
+    fn max_level(&self) -> u32 {
+        (0..self.height())
+            .flat_map(|y| (0..self.width()).map(move |x| (x, y)))
+            .map(|(x, y)| self.level(x, y))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Row-major grids of `bool` (on/off) or `u32` (brightness).
+impl<T: Copy + Into<u32>> Raster for Vec<Vec<T>> {
+    fn width(&self) -> usize {
+        self.first().map_or(0, |row| row.len())
+    }
+
+    fn height(&self) -> usize {
+        self.len()
+    }
+
+    fn level(&self, x: usize, y: usize) -> u32 {
+        self[y][x].into()
+    }
+}
+
+/// Binary PBM (`P4`). Lit cells are white, so the image reads like the lights.
+pub fn to_pbm(raster: &impl Raster) -> Vec<u8> {
+    let (width, height) = (raster.width(), raster.height());
+    let mut out = format!("P4\n{width} {height}\n").into_bytes();
+    for y in 0..height {
+        let mut row = vec![0u8; width.div_ceil(8)];
+        for x in 0..width {
+            if raster.level(x, y) == 0 {
+                row[x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+        out.extend(row);
+    }
+    out
+}
+
+/// Binary PGM (`P5`). The grey scale tops out at the brightest cell, so
+/// brightness grids keep their exact levels (16 bit when above 255).
+pub fn to_pgm(raster: &impl Raster) -> Vec<u8> {
+    let (width, height) = (raster.width(), raster.height());
+    let max_level = raster.max_level().clamp(1, u16::MAX as u32);
+    let mut out = format!("P5\n{width} {height}\n{max_level}\n").into_bytes();
+    for y in 0..height {
+        for x in 0..width {
+            let level = raster.level(x, y).min(max_level);
+            if max_level > 255 {
+                out.extend((level as u16).to_be_bytes());
+            } else {
+                out.push(level as u8);
+            }
+        }
+    }
+    out
+}
+
+/// 8 bit greyscale PNG, scaled so the brightest cell is white.
+/// The zlib stream uses stored blocks only, so no compression library is needed.
+pub fn to_png(raster: &impl Raster) -> Vec<u8> {
+    let (width, height) = (raster.width(), raster.height());
+    let max_level = raster.max_level().max(1) as u64;
+
+    let mut scanlines = Vec::with_capacity((width + 1) * height);
+    for y in 0..height {
+        scanlines.push(0); // filter: none
+        for x in 0..width {
+            scanlines.push((raster.level(x, y) as u64 * 255 / max_level) as u8);
+        }
+    }
+
+    let mut ihdr = vec![];
+    ihdr.extend((width as u32).to_be_bytes());
+    ihdr.extend((height as u32).to_be_bytes());
+    ihdr.extend([8, 0, 0, 0, 0]); // bit depth, greyscale, deflate, adaptive filter, no interlace
+
+    let mut out = vec![0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n'];
+    write_chunk(&mut out, b"IHDR", &ihdr);
+    write_chunk(&mut out, b"IDAT", &zlib_stored(&scanlines));
+    write_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+pub fn encode(raster: &impl Raster, format: ImageFormat) -> Vec<u8> {
+    match format {
+        ImageFormat::Pbm => to_pbm(raster),
+        ImageFormat::Pgm => to_pgm(raster),
+        ImageFormat::Png => to_png(raster),
+    }
+}
+
+pub fn write_image(path: impl AsRef<Path>, raster: &impl Raster, format: ImageFormat) -> io::Result<()> {
+    fs::write(path, encode(raster, format))
+}
+
+/// Writes numbered frames (`frame_0000.pgm`, `frame_0001.pgm`, ...) into a directory.
+pub struct FrameWriter {
+    dir: PathBuf,
+    format: ImageFormat,
+    next: usize,
+}
+
+impl FrameWriter {
+    pub fn new(dir: impl AsRef<Path>, format: ImageFormat) -> io::Result<Self> {
+        fs::create_dir_all(&dir)?;
+        Ok(FrameWriter { dir: dir.as_ref().to_path_buf(), format, next: 0 })
+    }
+
+    pub fn write(&mut self, raster: &impl Raster) -> io::Result<PathBuf> {
+        let path = self.dir.join(format!("frame_{:04}.{}", self.next, self.format.extension()));
+        write_image(&path, raster, self.format)?;
+        self.next += 1;
+        Ok(path)
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.next
+    }
+}
+
+fn write_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend((data.len() as u32).to_be_bytes());
+    out.extend(kind);
+    out.extend(data);
+    out.extend(crc32(kind.iter().chain(data)).to_be_bytes());
+}
+
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x78, 0x01];
+    let mut blocks = data.chunks(u16::MAX as usize).peekable();
+    if blocks.peek().is_none() {
+        out.extend([1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let len = block.len() as u16;
+        out.push(blocks.peek().is_none() as u8);
+        out.extend(len.to_le_bytes());
+        out.extend((!len).to_le_bytes());
+        out.extend(block);
+    }
+    out.extend(adler32(data).to_be_bytes());
+    out
+}
+
+fn crc32<'a>(bytes: impl IntoIterator<Item = &'a u8>) -> u32 {
+    !bytes.into_iter().fold(!0u32, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |c, _| {
+            if c & 1 == 1 { 0xedb8_8320 ^ (c >> 1) } else { c >> 1 }
+        })
+    })
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let (a, b) = bytes.iter().fold((1u32, 0u32), |(a, b), &byte| {
+        let a = (a + byte as u32) % 65521;
+        (a, (b + a) % 65521)
+    });
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_netpbm() {
+        let map = vec![
+            vec![true, false, true],
+            vec![false, true, false],
+        ];
+        assert_eq!(to_pbm(&map), b"P4\n3 2\n\x40\xa0".to_vec());
+        assert_eq!(to_pgm(&map), b"P5\n3 2\n1\n\x01\x00\x01\x00\x01\x00".to_vec());
+
+        let brightness: Vec<Vec<u32>> = vec![vec![0, 300]];
+        assert_eq!(to_pgm(&brightness), b"P5\n2 1\n300\n\x00\x00\x01\x2c".to_vec());
+    }
+
+    #[test]
+    fn test_png() {
+        assert_eq!(crc32(b"IEND"), 0xae42_6082);
+        assert_eq!(adler32(b"Wikipedia"), 0x11e6_0398);
+
+        let map: Vec<Vec<u32>> = vec![vec![0, 1, 2]];
+        let png = to_png(&map);
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+        assert_eq!(&png[png.len() - 12..], b"\x00\x00\x00\x00IEND\xae\x42\x60\x82");
+        // filter byte + three pixels scaled to 0..=255
+        let idat = png.windows(4).position(|w| w == b"IDAT").unwrap() + 4;
+        assert_eq!(&png[idat + 7..idat + 11], &[0, 0, 127, 255]);
+    }
+}
@@ -0,0 +1,289 @@
+/*
+ * Structural JSON traversal: visit every value with its path, skipping parts
+ * of the tree by pruning rules, or sum numbers straight off a reader.
+ * Example: `Walker::new().prune(Prune::ObjectValue("red".into())).sum(&value)`.
+ */
+use serde::de::{self, DeserializeSeed, IgnoredAny, MapAccess, SeqAccess};
+use serde_json::Value;
+use std::fmt::Display;
+use std::io::Read;
+
+pub mod query;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+impl Display for Segment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Segment::Key(key) if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') => {
+                write!(f, ".{key}")
+            },
+            Segment::Key(key) => write!(f, "[{}]", Value::String(key.clone())),
+            Segment::Index(idx) => write!(f, "[{idx}]"),
+        }
+    }
+}
+
+/// `$` for the root, then `.key` or `["odd key"]` and `[index]` steps.
+pub fn format_path(path: &[Segment]) -> String {
+    path.iter().fold("$".to_string(), |acc, segment| acc + &segment.to_string())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Prune {
+    /// Objects with this exact string as one of their values, and all they hold.
+    ObjectValue(String),
+    /// Object members stored under this key.
+    Key(String),
+    /// Anything nested deeper than this; the root is at depth 0.
+    Depth(usize),
+}
+
+pub trait Visitor {
+    fn visit(&mut self, path: &[Segment], value: &Value);
+}
+
+impl<F: FnMut(&[Segment], &Value)> Visitor for F {
+    fn visit(&mut self, path: &[Segment], value: &Value) {
+        self(path, value)
+    }
+}
+
+/// `value` as an `i64`: fractions are truncated, and numbers past either end
+/// saturate. Tree and streaming sums both convert through here.
+fn number(value: &serde_json::Number) -> i64 {
+    value.as_i64().unwrap_or_else(|| value.as_f64().unwrap_or_default() as i64)
+}
+
+/// Adds up every number it sees; fractions are truncated and the total saturates.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NumberSum {
+    pub total: i64,
+    pub count: usize,
+}
+
+impl Visitor for NumberSum {
+    fn visit(&mut self, _: &[Segment], value: &Value) {
+        if let Value::Number(n) = value {
+            self.total = self.total.saturating_add(number(n));
+            self.count += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Walker {
+    prunes: Vec<Prune>,
+}
+
+impl Walker {
+    pub fn new() -> Walker {
+        Walker::default()
+    }
+
+    pub fn prune(mut self, prune: Prune) -> Walker {
+        self.prunes.push(prune);
+        self
+    }
+
+    fn skips_key(&self, key: &str) -> bool {
+        self.prunes.iter().any(|prune| matches!(prune, Prune::Key(skipped) if skipped == key))
+    }
+
+    fn skips_depth(&self, depth: usize) -> bool {
+        self.prunes.iter().any(|prune| matches!(prune, Prune::Depth(max) if depth > *max))
+    }
+
+    fn skips_string(&self, text: &str) -> bool {
+        self.prunes.iter().any(|prune| matches!(prune, Prune::ObjectValue(skipped) if skipped == text))
+    }
+
+    /// Whether `value` is an object pruned for one of its values. Members
+    /// already pruned by key or depth do not count.
+    fn skips_object(&self, value: &Value, depth: usize) -> bool {
+        let Value::Object(members) = value else { return false };
+        !self.skips_depth(depth + 1) && members.iter()
+            .filter(|(key, _)| !self.skips_key(key))
+            .any(|(_, member)| matches!(member, Value::String(text) if self.skips_string(text)))
+    }
+
+    /// Calls `visitor` on every value that is not pruned, parents before
+    /// children, array elements in order and object members by key.
+    pub fn walk(&self, root: &Value, visitor: &mut impl Visitor) {
+        let mut path = vec![];
+        self.walk_at(root, &mut path, visitor);
+    }
+
+    fn walk_at(&self, value: &Value, path: &mut Vec<Segment>, visitor: &mut impl Visitor) {
+        if self.skips_depth(path.len()) || self.skips_object(value, path.len()) {
+            return;
+        }
+        visitor.visit(path, value);
+        match value {
+            Value::Array(items) => {
+                for (idx, item) in items.iter().enumerate() {
+                    path.push(Segment::Index(idx));
+                    self.walk_at(item, path, visitor);
+                    path.pop();
+                }
+            },
+            Value::Object(members) => {
+                for (key, member) in members.iter().filter(|(key, _)| !self.skips_key(key)) {
+                    path.push(Segment::Key(key.clone()));
+                    self.walk_at(member, path, visitor);
+                    path.pop();
+                }
+            },
+            _ => (),
+        }
+    }
+
+    pub fn sum(&self, root: &Value) -> i64 {
+        let mut sum = NumberSum::default();
+        self.walk(root, &mut sum);
+        sum.total
+    }
+
+    /// Same as `sum`, but reads the document as it goes instead of building
+    /// it first, so memory only grows with nesting depth.
+    pub fn sum_reader(&self, reader: impl Read) -> Result<i64, serde_json::Error> {
+        let mut deserializer = serde_json::Deserializer::from_reader(reader);
+        let streamed = StreamSum { walker: self, depth: 0 }.deserialize(&mut deserializer)?;
+        deserializer.end()?;
+        Ok(streamed.total)
+    }
+}
+
+/// What a value read by `StreamSum` adds up to, and whether it is a string its
+/// parent object gets pruned for.
+struct Streamed {
+    total: i64,
+    prunes_parent: bool,
+}
+
+impl Streamed {
+    fn number(total: i64) -> Streamed {
+        Streamed { total, prunes_parent: false }
+    }
+}
+
+struct StreamSum<'a> {
+    walker: &'a Walker,
+    depth: usize,
+}
+
+impl<'de> DeserializeSeed<'de> for StreamSum<'_> {
+    type Value = Streamed;
+
+    fn deserialize<D: de::Deserializer<'de>>(self, deserializer: D) -> Result<Streamed, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> de::Visitor<'de> for StreamSum<'_> {
+    type Value = Streamed;
+
+    fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "any JSON value")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Streamed, E> {
+        Ok(Streamed::number(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Streamed, E> {
+        Ok(Streamed::number(number(&value.into())))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Streamed, E> {
+        Ok(Streamed::number(serde_json::Number::from_f64(value).as_ref().map_or(0, number)))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Streamed, E> {
+        Ok(Streamed { total: 0, prunes_parent: self.walker.skips_string(value) })
+    }
+
+    fn visit_bool<E: de::Error>(self, _: bool) -> Result<Streamed, E> {
+        Ok(Streamed::number(0))
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Streamed, E> {
+        Ok(Streamed::number(0))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut items: A) -> Result<Streamed, A::Error> {
+        let mut total = 0;
+        if self.walker.skips_depth(self.depth + 1) {
+            while items.next_element::<IgnoredAny>()?.is_some() {}
+        } else {
+            while let Some(item) = items.next_element_seed(StreamSum { walker: self.walker, depth: self.depth + 1 })? {
+                total = item.total.saturating_add(total);
+            }
+        }
+        Ok(Streamed { total, prunes_parent: false })
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut members: A) -> Result<Streamed, A::Error> {
+        let (mut total, mut pruned) = (0, false);
+        while let Some(key) = members.next_key::<String>()? {
+            if pruned || self.walker.skips_key(&key) || self.walker.skips_depth(self.depth + 1) {
+                members.next_value::<IgnoredAny>()?;
+                continue;
+            }
+            let member = members.next_value_seed(StreamSum { walker: self.walker, depth: self.depth + 1 })?;
+            total = member.total.saturating_add(total);
+            pruned |= member.prunes_parent;
+        }
+        Ok(Streamed { total: if pruned { 0 } else { total }, prunes_parent: false })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_walk() {
+        let value: Value = serde_json::from_str(r#"{"a":[1,{"b c":2}],"d":"red"}"#).unwrap();
+        let mut paths = vec![];
+        Walker::new().walk(&value, &mut |path: &[Segment], _: &Value| paths.push(format_path(path)));
+        assert_eq!(paths, ["$", "$.a", "$.a[0]", "$.a[1]", r#"$.a[1]["b c"]"#, "$.d"]);
+
+        let red = Walker::new().prune(Prune::ObjectValue("red".to_string()));
+        assert_eq!(Walker::new().sum(&value), 3);
+        assert_eq!(red.sum(&value), 0);
+        assert_eq!(Walker::new().prune(Prune::Key("a".to_string())).sum(&value), 0);
+        assert_eq!(Walker::new().prune(Prune::Depth(2)).sum(&value), 1);
+
+        let reddish: Value = serde_json::from_str(r#"[1,{"c":"reddish","b":2},{"c":"red","b":9},"red"]"#).unwrap();
+        assert_eq!(red.sum(&reddish), 3);
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = r#"[1,{"c":"red","b":2},{"a":{"k":[4,-1.5],"n":"red"},"z":{"x":"red","y":{"q":7}}},{"k":"red","v":5}]"#;
+        let value: Value = serde_json::from_str(input).unwrap();
+        let walkers = [
+            Walker::new(),
+            Walker::new().prune(Prune::ObjectValue("red".to_string())),
+            Walker::new().prune(Prune::Key("k".to_string())),
+            Walker::new().prune(Prune::Depth(2)),
+            Walker::new().prune(Prune::Depth(1)).prune(Prune::ObjectValue("red".to_string())),
+            Walker::new().prune(Prune::Key("k".to_string())).prune(Prune::ObjectValue("red".to_string())),
+        ];
+        for walker in walkers {
+            assert_eq!(walker.sum_reader(input.as_bytes()).unwrap(), walker.sum(&value));
+        }
+        assert!(Walker::new().sum_reader("[1,".as_bytes()).is_err());
+
+        // past i64 both saturate the same way
+        let huge = "[18446744073709551615, 9223372036854775807, -1e300, 1e300]";
+        let value: Value = serde_json::from_str(huge).unwrap();
+        assert_eq!(Walker::new().sum(&value), i64::MAX - 1);
+        assert_eq!(Walker::new().sum_reader(huge.as_bytes()).unwrap(), Walker::new().sum(&value));
+    }
+}
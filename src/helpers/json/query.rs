@@ -0,0 +1,335 @@
+/*
+ * Queries over a JSON document: `<verb> <pattern> <clauses>`, where the
+ * pattern is a JSONPath subset ($, .key, ["key"], [0], [*], .*, ..) and the
+ * clauses filter matches or prune the walk.
+ * Example: `Query::parse("sum $..* is number prune-value \"red\"")?.run(&value)`.
+ */
+use super::{format_path, Prune, Segment, Walker};
+use serde_json::Value;
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    Empty,
+    UnknownVerb(String),
+    Pattern(String),
+    Clause(String),
+}
+
+impl Display for QueryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QueryError::Empty => write!(f, "empty query, expected `<verb> <pattern> <clauses>`"),
+            QueryError::UnknownVerb(verb) => write!(f, "unknown verb `{verb}`, expected paths, values, count or sum"),
+            QueryError::Pattern(text) => write!(f, "cannot parse pattern `{text}`"),
+            QueryError::Clause(text) => write!(f, "cannot parse clause `{text}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verb {
+    /// Every matched path.
+    Paths,
+    /// Every matched path with its value.
+    Values,
+    Count,
+    /// Sum of the matched numbers.
+    Sum,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Key(String),
+    Index(usize),
+    /// `*`: any one member or element.
+    Any,
+    /// `..`: any number of levels, none included.
+    Descend,
+}
+
+impl Step {
+    fn accepts(&self, segment: &Segment) -> bool {
+        match (self, segment) {
+            (Step::Any, _) => true,
+            (Step::Key(key), Segment::Key(other)) => key == other,
+            (Step::Index(idx), Segment::Index(other)) => idx == other,
+            _ => false,
+        }
+    }
+}
+
+/// Whether the concrete `path` fits `steps`.
+pub fn matches(steps: &[Step], path: &[Segment]) -> bool {
+    match steps.split_first() {
+        None => path.is_empty(),
+        Some((Step::Descend, rest)) => (0..=path.len()).any(|skip| matches(rest, &path[skip..])),
+        Some((step, rest)) => path.split_first().is_some_and(|(segment, tail)| step.accepts(segment) && matches(rest, tail)),
+    }
+}
+
+pub fn parse_pattern(text: &str) -> Result<Vec<Step>, QueryError> {
+    let error = || QueryError::Pattern(text.to_string());
+    let mut rest = text.strip_prefix('$').ok_or_else(error)?;
+    let mut steps = vec![];
+    while !rest.is_empty() {
+        let dotted = if let Some(after) = rest.strip_prefix("..") {
+            steps.push(Step::Descend);
+            rest = after;
+            true
+        } else if let Some(after) = rest.strip_prefix('.') {
+            rest = after;
+            true
+        } else {
+            false
+        };
+        if let Some(after) = rest.strip_prefix('[') {
+            if after.starts_with('"') {
+                // a quoted key may hold `]`, so it ends where its JSON string does
+                let mut keys = serde_json::Deserializer::from_str(after).into_iter::<String>();
+                let key = keys.next().and_then(Result::ok).ok_or_else(error)?;
+                steps.push(Step::Key(key));
+                rest = after[keys.byte_offset()..].strip_prefix(']').ok_or_else(error)?;
+                continue;
+            }
+            let (inside, after) = after.split_once(']').ok_or_else(error)?;
+            steps.push(match inside {
+                "*" => Step::Any,
+                _ => Step::Index(inside.parse().map_err(|_| error())?),
+            });
+            rest = after;
+        } else if dotted {
+            let end = rest.find(['.', '[']).unwrap_or(rest.len());
+            steps.push(match &rest[..end] {
+                "" => return Err(error()),
+                "*" => Step::Any,
+                key => Step::Key(key.to_string()),
+            });
+            rest = &rest[end..];
+        } else {
+            return Err(error());
+        }
+    }
+    if steps.last() == Some(&Step::Descend) {
+        return Err(error());
+    }
+    Ok(steps)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cmp {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+}
+
+impl Cmp {
+    pub fn holds(&self, left: usize, right: usize) -> bool {
+        match self {
+            Cmp::Gt => left > right,
+            Cmp::Ge => left >= right,
+            Cmp::Lt => left < right,
+            Cmp::Le => left <= right,
+            Cmp::Eq => left == right,
+        }
+    }
+}
+
+impl FromStr for Cmp {
+    type Err = QueryError;
+
+    fn from_str(text: &str) -> Result<Cmp, QueryError> {
+        match text {
+            ">" => Ok(Cmp::Gt),
+            ">=" => Ok(Cmp::Ge),
+            "<" => Ok(Cmp::Lt),
+            "<=" => Ok(Cmp::Le),
+            "==" => Ok(Cmp::Eq),
+            _ => Err(QueryError::Clause(text.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// `is number|string|array|object|bool|null`
+    Kind(String),
+    /// `depth > N` (also `>=`, `<`, `<=`, `==`); the root is at depth 0.
+    Depth(Cmp, usize),
+    /// `== <json>`
+    Equals(Value),
+}
+
+impl Filter {
+    fn keeps(&self, path: &[Segment], value: &Value) -> bool {
+        match self {
+            Filter::Kind(kind) => kind == match value {
+                Value::Null => "null",
+                Value::Bool(_) => "bool",
+                Value::Number(_) => "number",
+                Value::String(_) => "string",
+                Value::Array(_) => "array",
+                Value::Object(_) => "object",
+            },
+            Filter::Depth(cmp, depth) => cmp.holds(path.len(), *depth),
+            Filter::Equals(expected) => value == expected,
+        }
+    }
+}
+
+/// Splits on whitespace outside double quotes.
+fn tokenize(text: &str) -> Vec<&str> {
+    let (mut tokens, mut start, mut quoted, mut escaped) = (vec![], None, false, false);
+    for (idx, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if let Some(from) = start.take() {
+                    tokens.push(&text[from..idx]);
+                }
+                continue;
+            },
+            _ => (),
+        }
+        start.get_or_insert(idx);
+    }
+    if let Some(from) = start {
+        tokens.push(&text[from..]);
+    }
+    tokens
+}
+
+#[derive(Debug, Clone)]
+pub struct Query {
+    pub verb: Verb,
+    pub pattern: Vec<Step>,
+    pub filters: Vec<Filter>,
+    pub walker: Walker,
+}
+
+/// What a query found: matched paths with their values, and its verb.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Matches {
+    pub verb: Verb,
+    pub found: Vec<(String, Value)>,
+}
+
+impl Query {
+    /// Clauses: `is <kind>`, `depth <op> <n>`, `== <json>`, and the pruning
+    /// `prune-value <json string>`, `prune-key <json string>`, `prune-depth <n>`.
+    pub fn parse(text: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(text);
+        let (&verb, rest) = tokens.split_first().ok_or(QueryError::Empty)?;
+        let verb = match verb {
+            "paths" => Verb::Paths,
+            "values" => Verb::Values,
+            "count" => Verb::Count,
+            "sum" => Verb::Sum,
+            _ => return Err(QueryError::UnknownVerb(verb.to_string())),
+        };
+        let (&pattern, mut rest) = rest.split_first().ok_or_else(|| QueryError::Pattern(String::new()))?;
+        let mut query = Query { verb, pattern: parse_pattern(pattern)?, filters: vec![], walker: Walker::new() };
+        while let Some((&clause, after)) = rest.split_first() {
+            let text = rest.join(" ");
+            let error = || QueryError::Clause(text.clone());
+            let (&argument, after) = after.split_first().ok_or_else(error)?;
+            rest = after;
+            match clause {
+                "is" if ["number", "string", "array", "object", "bool", "null"].contains(&argument) => {
+                    query.filters.push(Filter::Kind(argument.to_string()));
+                },
+                "depth" => {
+                    let cmp = argument.parse::<Cmp>().map_err(|_| error())?;
+                    let (&depth, after) = rest.split_first().ok_or_else(error)?;
+                    query.filters.push(Filter::Depth(cmp, depth.parse().map_err(|_| error())?));
+                    rest = after;
+                },
+                "==" => query.filters.push(Filter::Equals(serde_json::from_str(argument).map_err(|_| error())?)),
+                "prune-value" => {
+                    let text = serde_json::from_str::<String>(argument).map_err(|_| error())?;
+                    query.walker = query.walker.prune(Prune::ObjectValue(text));
+                },
+                "prune-key" => {
+                    let key = serde_json::from_str::<String>(argument).map_err(|_| error())?;
+                    query.walker = query.walker.prune(Prune::Key(key));
+                },
+                "prune-depth" => query.walker = query.walker.prune(Prune::Depth(argument.parse().map_err(|_| error())?)),
+                _ => return Err(error()),
+            }
+        }
+        Ok(query)
+    }
+
+    pub fn run(&self, root: &Value) -> Matches {
+        let mut found = vec![];
+        self.walker.walk(root, &mut |path: &[Segment], value: &Value| {
+            if matches(&self.pattern, path) && self.filters.iter().all(|filter| filter.keeps(path, value)) {
+                found.push((format_path(path), value.clone()));
+            }
+        });
+        Matches { verb: self.verb, found }
+    }
+}
+
+impl Matches {
+    pub fn sum(&self) -> i64 {
+        self.found.iter().filter_map(|(_, value)| value.as_number()).map(super::number).fold(0, i64::saturating_add)
+    }
+}
+
+impl Display for Matches {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.verb {
+            Verb::Paths => self.found.iter().try_for_each(|(path, _)| writeln!(f, "{path}")),
+            Verb::Values => self.found.iter().try_for_each(|(path, value)| writeln!(f, "{path} = {value}")),
+            Verb::Count => writeln!(f, "{}", self.found.len()),
+            Verb::Sum => writeln!(f, "{}", self.sum()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pattern() {
+        assert_eq!(parse_pattern(r#"$.a[0]..*["b c"]"#), Ok(vec![
+            Step::Key("a".to_string()), Step::Index(0), Step::Descend, Step::Any, Step::Key("b c".to_string()),
+        ]));
+        assert!(parse_pattern("a.b").is_err());
+        assert!(parse_pattern("$..").is_err());
+        assert_eq!(parse_pattern(r#"$["a]b"]["q\"]"][1]"#), Ok(vec![
+            Step::Key("a]b".to_string()), Step::Key("q\"]".to_string()), Step::Index(1),
+        ]));
+        assert!(parse_pattern(r#"$["a"x]"#).is_err());
+        let path = [Segment::Key("a".to_string()), Segment::Index(2), Segment::Key("x".to_string())];
+        assert!(matches(&parse_pattern("$..x").unwrap(), &path));
+        assert!(matches(&parse_pattern("$.a[*].x").unwrap(), &path));
+        assert!(!matches(&parse_pattern("$.a.x").unwrap(), &path));
+        assert!(matches(&parse_pattern("$").unwrap(), &[]));
+    }
+
+    #[test]
+    fn test_query() {
+        let value: Value = serde_json::from_str(r#"[1,{"c":"red","b":2},{"d":[[3]],"e":"x y"}]"#).unwrap();
+        let run = |text: &str| Query::parse(text).unwrap().run(&value);
+        assert_eq!(run("sum $..* is number").sum(), 6);
+        assert_eq!(run(r#"sum $..* is number prune-value "red""#).sum(), 4);
+        assert_eq!(run("count $..* is array depth > 1").found.len(), 2);
+        assert_eq!(run("paths $..* is number").to_string(), "$[0]\n$[1].b\n$[2].d[0][0]\n");
+        assert_eq!(run(r#"values $..e == "x y""#).to_string(), "$[2].e = \"x y\"\n");
+        assert_eq!(run(r#"count $..* prune-key "d" prune-depth 1"#).found.len(), 3);
+        assert_eq!(run(r#"count $..* prune-key "d" is array"#).found.len(), 0);
+
+        assert_eq!(Query::parse("find $").unwrap_err(), QueryError::UnknownVerb("find".to_string()));
+        assert_eq!(Query::parse("sum $ depth >").unwrap_err(), QueryError::Clause("depth >".to_string()));
+        assert_eq!(Query::parse("sum $ depth => 1").unwrap_err(), QueryError::Clause("depth => 1".to_string()));
+        assert_eq!(Query::parse("sum $ prune-key d").unwrap_err(), QueryError::Clause("prune-key d".to_string()));
+    }
+}
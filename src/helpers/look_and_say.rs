@@ -0,0 +1,326 @@
+/*
+ * Look-and-say sequences, expanded directly or counted through Conway's
+ * element decomposition: every string falls apart into atoms that evolve
+ * independently, so only how many of each atom there are needs tracking.
+ * Example: `look_and_say::length_after("1113222113", 50)`.
+ */
+use itertools::Itertools;
+use std::collections::HashMap;
+
+/// Up to this many rounds, `length_after` builds the string itself.
+pub const DIRECT_ROUNDS: usize = 12;
+
+/// Rounds after which a string is old enough for the splitting theorem.
+const SETTLE_ROUNDS: usize = 2;
+
+/// Conway's 92 common elements, hydrogen to uranium: name, digits and the
+/// elements it decays into after one round.
+pub const ELEMENTS: [(&str, &str, &str); 92] = [
+    ("H", "22", "H"),
+    ("He", "13112221133211322112211213322112", "Hf.Pa.H.Ca.Li"),
+    ("Li", "312211322212221121123222112", "He"),
+    ("Be", "111312211312113221133211322112211213322112", "Ge.Ca.Li"),
+    ("B", "1321132122211322212221121123222112", "Be"),
+    ("C", "3113112211322112211213322112", "B"),
+    ("N", "111312212221121123222112", "C"),
+    ("O", "132112211213322112", "N"),
+    ("F", "31121123222112", "O"),
+    ("Ne", "111213322112", "F"),
+    ("Na", "123222112", "Ne"),
+    ("Mg", "3113322112", "Pm.Na"),
+    ("Al", "1113222112", "Mg"),
+    ("Si", "1322112", "Al"),
+    ("P", "311311222112", "Ho.Si"),
+    ("S", "1113122112", "P"),
+    ("Cl", "132112", "S"),
+    ("Ar", "3112", "Cl"),
+    ("K", "1112", "Ar"),
+    ("Ca", "12", "K"),
+    ("Sc", "3113112221133112", "Ho.Pa.H.Ca.Co"),
+    ("Ti", "11131221131112", "Sc"),
+    ("V", "13211312", "Ti"),
+    ("Cr", "31132", "V"),
+    ("Mn", "111311222112", "Cr.Si"),
+    ("Fe", "13122112", "Mn"),
+    ("Co", "32112", "Fe"),
+    ("Ni", "11133112", "Zn.Co"),
+    ("Cu", "131112", "Ni"),
+    ("Zn", "312", "Cu"),
+    ("Ga", "13221133122211332", "Eu.Ca.Ac.H.Ca.Zn"),
+    ("Ge", "31131122211311122113222", "Ho.Ga"),
+    ("As", "11131221131211322113322112", "Ge.Na"),
+    ("Se", "13211321222113222112", "As"),
+    ("Br", "3113112211322112", "Se"),
+    ("Kr", "11131221222112", "Br"),
+    ("Rb", "1321122112", "Kr"),
+    ("Sr", "3112112", "Rb"),
+    ("Y", "1112133", "Sr.U"),
+    ("Zr", "12322211331222113112211", "Y.H.Ca.Tc"),
+    ("Nb", "1113122113322113111221131221", "Er.Zr"),
+    ("Mo", "13211322211312113211", "Nb"),
+    ("Tc", "311322113212221", "Mo"),
+    ("Ru", "132211331222113112211", "Eu.Ca.Tc"),
+    ("Rh", "311311222113111221131221", "Ho.Ru"),
+    ("Pd", "111312211312113211", "Rh"),
+    ("Ag", "132113212221", "Pd"),
+    ("Cd", "3113112211", "Ag"),
+    ("In", "11131221", "Cd"),
+    ("Sn", "13211", "In"),
+    ("Sb", "3112221", "Pm.Sn"),
+    ("Te", "1322113312211", "Eu.Ca.Sb"),
+    ("I", "311311222113111221", "Ho.Te"),
+    ("Xe", "11131221131211", "I"),
+    ("Cs", "13211321", "Xe"),
+    ("Ba", "311311", "Cs"),
+    ("La", "11131", "Ba"),
+    ("Ce", "1321133112", "La.H.Ca.Co"),
+    ("Pr", "31131112", "Ce"),
+    ("Nd", "111312", "Pr"),
+    ("Pm", "132", "Nd"),
+    ("Sm", "311332", "Pm.Ca.Zn"),
+    ("Eu", "1113222", "Sm"),
+    ("Gd", "13221133112", "Eu.Ca.Co"),
+    ("Tb", "3113112221131112", "Ho.Gd"),
+    ("Dy", "111312211312", "Tb"),
+    ("Ho", "1321132", "Dy"),
+    ("Er", "311311222", "Ho.Pm"),
+    ("Tm", "11131221133112", "Er.Ca.Co"),
+    ("Yb", "1321131112", "Tm"),
+    ("Lu", "311312", "Yb"),
+    ("Hf", "11132", "Lu"),
+    ("Ta", "13112221133211322112211213322113", "Hf.Pa.H.Ca.W"),
+    ("W", "312211322212221121123222113", "Ta"),
+    ("Re", "111312211312113221133211322112211213322113", "Ge.Ca.W"),
+    ("Os", "1321132122211322212221121123222113", "Re"),
+    ("Ir", "3113112211322112211213322113", "Os"),
+    ("Pt", "111312212221121123222113", "Ir"),
+    ("Au", "132112211213322113", "Pt"),
+    ("Hg", "31121123222113", "Au"),
+    ("Tl", "111213322113", "Hg"),
+    ("Pb", "123222113", "Tl"),
+    ("Bi", "3113322113", "Pm.Pb"),
+    ("Po", "1113222113", "Bi"),
+    ("At", "1322113", "Po"),
+    ("Rn", "311311222113", "Ho.At"),
+    ("Fr", "1113122113", "Rn"),
+    ("Ra", "132113", "Fr"),
+    ("Ac", "3113", "Ra"),
+    ("Th", "1113", "Ac"),
+    ("Pa", "13", "Th"),
+    ("U", "3", "Pa"),
+];
+
+/// One round: every run of equal digits becomes its length followed by the digit.
+pub fn expand(digits: &str) -> String {
+    let bytes = digits.as_bytes();
+    let mut out = String::with_capacity(bytes.len() * 2);
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let run = bytes[idx..].iter().take_while(|&&b| b == bytes[idx]).count();
+        out += &run.to_string();
+        out.push(bytes[idx] as char);
+        idx += run;
+    }
+    out
+}
+
+/// `rounds` rounds of `expand`.
+pub fn expand_n(digits: &str, rounds: usize) -> String {
+    (0..rounds).fold(digits.to_string(), |acc, _| expand(&acc))
+}
+
+/// Digits other than 1, 2 and 3, which only survive from the starting string.
+fn heavy(digit: u8) -> bool {
+    !(b'1'..=b'3').contains(&digit)
+}
+
+/// Whether a right side with these `(length, digit)` runs may follow a 2:
+/// it starts with 1^1 X^1, 1^3, 3^1 X^(not 3) or n^(0 or 1).
+fn splits_after_two(runs: &[(usize, u8)]) -> bool {
+    match runs {
+        [] | [(1, b'1'), (1, _), ..] | [(3, b'1'), ..] | [(1, b'3')] => true,
+        [(1, b'3'), (count, _), ..] => *count != 3,
+        [(1, digit), ..] => heavy(*digit),
+        _ => false,
+    }
+}
+
+/// Conway's splitting theorem: a string at least two rounds old evolves as
+/// `left` and `right` side by side forever exactly when `left` ends in n and
+/// `right` starts with 1, 2 or 3, or `left` ends in 2 and `right` is of a form
+/// in `splits_after_two`, or `left` ends in anything else and `right` is 2^2
+/// followed by such a form. Here n is any digit but 1, 2 and 3.
+fn splits(left: &str, right: &str) -> bool {
+    let runs = right.bytes().dedup_with_count().collect::<Vec<_>>();
+    match (left.as_bytes().last(), runs.as_slice()) {
+        (None, _) | (_, []) => true,
+        (Some(&last), [(_, first), ..]) if heavy(last) && !heavy(*first) => true,
+        (Some(b'2'), runs) => splits_after_two(runs),
+        (Some(_), [(2, b'2'), rest @ ..]) => splits_after_two(rest),
+        _ => false,
+    }
+}
+
+/// Cuts `digits`, at least two rounds old, at every point where the two
+/// sides never interact again.
+pub fn split_atoms(digits: &str) -> Vec<&str> {
+    let bytes = digits.as_bytes();
+    let mut atoms = vec![];
+    let mut start = 0;
+    for idx in 1..bytes.len() {
+        if bytes[idx - 1] != bytes[idx] && splits(&digits[start..idx], &digits[idx..]) {
+            atoms.push(&digits[start..idx]);
+            start = idx;
+        }
+    }
+    if start < bytes.len() {
+        atoms.push(&digits[start..]);
+    }
+    atoms
+}
+
+/// Atoms seen so far and what each one turns into after a round.
+#[derive(Debug, Clone, Default)]
+pub struct Chemistry {
+    atoms: Vec<String>,
+    index: HashMap<String, usize>,
+    decays: Vec<Vec<usize>>,
+}
+
+impl Chemistry {
+    /// Knows no atoms yet and learns them as they show up.
+    pub fn new() -> Chemistry {
+        Chemistry::default()
+    }
+
+    /// Knows the 92 common elements, in `ELEMENTS` order.
+    pub fn common() -> Chemistry {
+        let mut chemistry = Chemistry::new();
+        let element_id = |name: &str| ELEMENTS.iter().position(|&(element, _, _)| element == name).unwrap();
+        for (id, (_, digits, decay)) in ELEMENTS.iter().enumerate() {
+            chemistry.atoms.push(digits.to_string());
+            chemistry.index.insert(digits.to_string(), id);
+            chemistry.decays.push(decay.split('.').map(element_id).collect());
+        }
+        chemistry
+    }
+
+    pub fn len(&self) -> usize {
+        self.atoms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.atoms.is_empty()
+    }
+
+    pub fn atom(&self, id: usize) -> &str {
+        &self.atoms[id]
+    }
+
+    pub fn decay(&self, id: usize) -> &[usize] {
+        &self.decays[id]
+    }
+
+    /// Id of `atom`, learning it and everything it ever decays into.
+    fn intern(&mut self, atom: &str) -> usize {
+        if let Some(&id) = self.index.get(atom) {
+            return id;
+        }
+        let id = self.atoms.len();
+        self.atoms.push(atom.to_string());
+        self.index.insert(atom.to_string(), id);
+        self.decays.push(vec![]);
+        let expanded = expand(atom);
+        let decay = split_atoms(&expanded).into_iter().map(|child| self.intern(child)).collect();
+        self.decays[id] = decay;
+        id
+    }
+
+    /// Atom ids of `digits`, which must be at least two rounds old, in order.
+    pub fn decompose(&mut self, digits: &str) -> Vec<usize> {
+        split_atoms(digits).into_iter().map(|atom| self.intern(atom)).collect()
+    }
+
+    /// Length of `digits` after `rounds` rounds, `None` past `u128`.
+    pub fn length_after(&mut self, digits: &str, rounds: usize) -> Option<u128> {
+        let settled = rounds.min(SETTLE_ROUNDS);
+        let mut counts = vec![0u128; self.len()];
+        for id in self.decompose(&expand_n(digits, settled)) {
+            counts.resize(self.len(), 0);
+            counts[id] += 1;
+        }
+        counts.resize(self.len(), 0);
+        for _ in settled..rounds {
+            let mut next = vec![0u128; self.len()];
+            for (id, &count) in counts.iter().enumerate().filter(|(_, &count)| count > 0) {
+                for &child in &self.decays[id] {
+                    next[child] = next[child].checked_add(count)?;
+                }
+            }
+            counts = next;
+        }
+        counts.iter().enumerate().try_fold(0u128, |total, (id, &count)| {
+            total.checked_add(count.checked_mul(self.atoms[id].len() as u128)?)
+        })
+    }
+}
+
+/// Length of `digits` after `rounds` rounds, expanding directly for few rounds.
+pub fn length_after(digits: &str, rounds: usize) -> Option<u128> {
+    if rounds <= DIRECT_ROUNDS {
+        return Some(expand_n(digits, rounds).len() as u128);
+    }
+    Chemistry::common().length_after(digits, rounds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand_n("1", 5), "312211");
+        assert_eq!(expand("1111111111"), "101");
+        assert_eq!(split_atoms("22"), ["22"]);
+        // "12" starts its descendants with 1, 1, 3, ... and never 2
+        assert_eq!(split_atoms("2212"), ["22", "12"]);
+        // "222" becomes "32", then "1312", which would merge with the 1
+        assert_eq!(split_atoms("1222"), ["1222"]);
+        // 3^1 X^3 does not split after a 2, 3^1 X^2 does
+        assert_eq!(split_atoms("23111"), ["23111"]);
+        assert_eq!(split_atoms("2311"), ["2", "311"]);
+        assert_eq!(split_atoms("4213"), ["4", "2", "13"]);
+    }
+
+    #[test]
+    fn test_elements() {
+        // learning from uranium finds exactly Conway's table
+        let mut chemistry = Chemistry::new();
+        chemistry.decompose("3");
+        assert_eq!(chemistry.len(), 92);
+        let common = Chemistry::common();
+        for (id, &(name, digits, decay)) in ELEMENTS.iter().enumerate() {
+            let learned = chemistry.decompose(digits);
+            assert_eq!(learned.len(), 1, "{name} is one atom");
+            let children = chemistry.decay(learned[0]).iter().map(|&child| chemistry.atom(child)).collect::<Vec<_>>();
+            let table = common.decay(id).iter().map(|&child| common.atom(child)).collect::<Vec<_>>();
+            assert_eq!(children, table, "{name} decays into {decay}");
+            assert_eq!(table.concat(), expand(digits));
+        }
+        assert_eq!(chemistry.len(), 92);
+    }
+
+    #[test]
+    fn test_cross_check() {
+        let mut chemistry = Chemistry::new();
+        for seed in ["1", "1113222113", "3113322113", "1321131112", "4422211111111111"] {
+            for rounds in 0..=30 {
+                let direct = expand_n(seed, rounds).len() as u128;
+                assert_eq!(chemistry.length_after(seed, rounds), Some(direct), "{seed} after {rounds}");
+            }
+        }
+        assert_eq!(length_after("1113222113", 40), Some(expand_n("1113222113", 40).len() as u128));
+        assert!(length_after("1", 300).is_some());
+        assert_eq!(length_after("1", 1000), None);
+    }
+}
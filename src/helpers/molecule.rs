@@ -0,0 +1,475 @@
+/*
+ * Molecule replacements: molecules are split into element tokens, rules
+ * rewrite one token into several. Finds one-step successors, derivations of
+ * a molecule from `e` by greedy reverse reduction with random restarts, and
+ * the exact fewest steps with a CYK parse.
+ * Example: `let (rules, molecule) = molecule::parse(input)?; rules.fewest_steps(&molecule)`.
+ */
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Display;
+
+lazy_static! {
+    static ref REPLACEMENT_REGEX: Regex = Regex::new(r"^(?<from>\w+) => (?<to>\w+)$").unwrap();
+    static ref MOLECULE_REGEX: Regex = Regex::new(r"e|[A-Z][a-z]?").unwrap();
+}
+
+pub type Atom = usize;
+
+/// `e`, every derivation starts from it.
+pub const START: Atom = 0;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MoleculeError {
+    Parse { line: usize, text: String },
+    MissingMolecule,
+}
+
+impl Display for MoleculeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MoleculeError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            MoleculeError::MissingMolecule => write!(f, "no molecule after the replacements"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DerivationError {
+    Parse { line: usize, text: String },
+    Empty,
+    Ends,
+    NotOneStep { line: usize },
+}
+
+impl Display for DerivationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DerivationError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            DerivationError::Empty => write!(f, "the derivation is empty"),
+            DerivationError::Ends => write!(f, "the derivation has to lead from `e` to the molecule, either way round"),
+            DerivationError::NotOneStep { line } => write!(f, "line {line}: not one replacement away from the line before"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub from: Atom,
+    pub to: Vec<Atom>,
+}
+
+/// Rule `rule` applied to the token at `position`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Step {
+    pub position: usize,
+    pub rule: usize,
+}
+
+/// Tiny xorshift generator, enough to shuffle rules between restarts.
+#[derive(Debug, Clone)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn shuffle<T>(&mut self, items: &mut [T]) {
+        for idx in (1..items.len()).rev() {
+            items.swap(idx, (self.next() % (idx as u64 + 1)) as usize);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Replacements {
+    atoms: Vec<String>,
+    index: HashMap<String, Atom>,
+    pub rules: Vec<Rule>,
+}
+
+/// Rules, a blank line, then the molecule.
+pub fn parse(input: &str) -> Result<(Replacements, Vec<Atom>), MoleculeError> {
+    let mut replacements = Replacements::new();
+    let mut lines = input.lines().enumerate();
+    for (idx, line) in lines.by_ref().take_while(|(_, line)| !line.trim().is_empty()) {
+        let error = || MoleculeError::Parse { line: idx + 1, text: line.to_string() };
+        let captured = REPLACEMENT_REGEX.captures(line.trim()).ok_or_else(error)?;
+        let from = replacements.tokenize(&captured["from"]).filter(|from| from.len() == 1).ok_or_else(error)?;
+        let to = replacements.tokenize(&captured["to"]).ok_or_else(error)?;
+        replacements.rules.push(Rule { from: from[0], to });
+    }
+    let (idx, line) = lines.find(|(_, line)| !line.trim().is_empty()).ok_or(MoleculeError::MissingMolecule)?;
+    let molecule = replacements.tokenize(line.trim()).ok_or_else(|| MoleculeError::Parse { line: idx + 1, text: line.to_string() })?;
+    Ok((replacements, molecule))
+}
+
+impl Default for Replacements {
+    fn default() -> Replacements {
+        Replacements::new()
+    }
+}
+
+impl Replacements {
+    pub fn new() -> Replacements {
+        let mut replacements = Replacements { atoms: vec![], index: HashMap::new(), rules: vec![] };
+        replacements.intern("e");
+        replacements
+    }
+
+    fn intern(&mut self, atom: &str) -> Atom {
+        if let Some(&id) = self.index.get(atom) {
+            return id;
+        }
+        self.atoms.push(atom.to_string());
+        self.index.insert(atom.to_string(), self.atoms.len() - 1);
+        self.atoms.len() - 1
+    }
+
+    /// Element tokens of `text`, `None` unless they cover all of it.
+    pub fn tokenize(&mut self, text: &str) -> Option<Vec<Atom>> {
+        let tokens = MOLECULE_REGEX.find_iter(text).map(|found| found.as_str()).collect::<Vec<_>>();
+        if text.is_empty() || tokens.concat() != text {
+            return None;
+        }
+        Some(tokens.into_iter().map(|token| self.intern(token)).collect())
+    }
+
+    pub fn name(&self, atom: Atom) -> &str {
+        &self.atoms[atom]
+    }
+
+    pub fn render(&self, molecule: &[Atom]) -> String {
+        molecule.iter().map(|&atom| self.name(atom)).collect()
+    }
+
+    /// `molecule` with `step` applied, if its rule fits there.
+    pub fn apply(&self, molecule: &[Atom], step: Step) -> Option<Vec<Atom>> {
+        let rule = self.rules.get(step.rule)?;
+        (molecule.get(step.position) == Some(&rule.from)).then(|| {
+            [&molecule[..step.position], &rule.to, &molecule[step.position + 1..]].concat()
+        })
+    }
+
+    /// `molecule` with `step` taken back, if its rule's result sits there.
+    /// Rules from `e` only take back a whole molecule.
+    pub fn undo(&self, molecule: &[Atom], step: Step) -> Option<Vec<Atom>> {
+        let rule = self.rules.get(step.rule)?;
+        let end = step.position + rule.to.len();
+        let fits = molecule.get(step.position..end) == Some(&rule.to[..])
+            && (rule.from != START || molecule.len() == rule.to.len());
+        fits.then(|| [&molecule[..step.position], &[rule.from], &molecule[end..]].concat())
+    }
+
+    /// The step turning `before` into `after`, if there is one.
+    pub fn step_between(&self, before: &[Atom], after: &[Atom]) -> Option<Step> {
+        // the step has to land at or before the first difference
+        let first = before.iter().zip(after).take_while(|(a, b)| a == b).count();
+        (0..=first.min(before.len().saturating_sub(1)))
+            .flat_map(|position| (0..self.rules.len()).map(move |rule| Step { position, rule }))
+            .find(|&step| self.apply(before, step).as_deref() == Some(after))
+    }
+
+    /// `rule` at `position`, like `H => HO at 2`.
+    pub fn describe(&self, step: Step) -> String {
+        let rule = &self.rules[step.rule];
+        format!("{} => {} at {}", self.name(rule.from), self.render(&rule.to), step.position)
+    }
+
+    /// Every molecule along `steps` taken from `e`, `e` included.
+    pub fn molecules(&self, steps: &[Step]) -> Option<Vec<Vec<Atom>>> {
+        let mut molecules = vec![vec![START]];
+        for &step in steps {
+            molecules.push(self.apply(molecules.last()?, step)?);
+        }
+        Some(molecules)
+    }
+
+    /// Checks a derivation written one molecule per line, from `e` to
+    /// `target` or back; blank lines and anything after `#` are ignored.
+    /// Returns its steps from `e`.
+    pub fn check_derivation(&mut self, text: &str, target: &[Atom]) -> Result<Vec<Step>, DerivationError> {
+        let mut lines = vec![];
+        for (idx, line) in text.lines().enumerate() {
+            let molecule = line.split('#').next().unwrap_or_default().trim();
+            if molecule.is_empty() {
+                continue;
+            }
+            let atoms = self.tokenize(molecule).ok_or_else(|| DerivationError::Parse { line: idx + 1, text: line.to_string() })?;
+            lines.push((idx + 1, atoms));
+        }
+        let (Some((_, first)), Some((_, last))) = (lines.first(), lines.last()) else { return Err(DerivationError::Empty) };
+        match (first == &[START], last == target) {
+            (true, true) => (),
+            _ if first == target && last == &[START] => lines.reverse(),
+            _ => return Err(DerivationError::Ends),
+        }
+        lines.windows(2).map(|pair| {
+            let ((before_line, before), (after_line, after)) = (&pair[0], &pair[1]);
+            let line = *before_line.max(after_line);
+            self.step_between(before, after).ok_or(DerivationError::NotOneStep { line })
+        }).collect()
+    }
+
+    /// Every distinct molecule one replacement away.
+    pub fn successors(&self, molecule: &[Atom]) -> HashSet<Vec<Atom>> {
+        (0..molecule.len())
+            .flat_map(|position| (0..self.rules.len()).map(move |rule| Step { position, rule }))
+            .filter_map(|step| self.apply(molecule, step))
+            .collect()
+    }
+
+    /// Steps leading from `e` to `molecule`, found by taking back the first
+    /// rule that fits, in an order shuffled anew for each of `restarts`
+    /// attempts. Quick, but neither sure to succeed nor to be the shortest.
+    pub fn reduce_greedy(&self, molecule: &[Atom], restarts: usize, seed: u64) -> Option<Vec<Step>> {
+        let mut rng = XorShift(seed.max(1));
+        let mut order = (0..self.rules.len()).collect::<Vec<_>>();
+        // rules like `H => O` could go round in circles
+        let limit = molecule.len() * (self.rules.len() + 1);
+        for _ in 0..restarts {
+            rng.shuffle(&mut order);
+            let (mut current, mut steps) = (molecule.to_vec(), vec![]);
+            while current != [START] && steps.len() < limit {
+                let undone = order.iter().find_map(|&rule| {
+                    (0..current.len()).map(|position| Step { position, rule })
+                        .find_map(|step| Some((step, self.undo(&current, step)?)))
+                });
+                let Some((step, previous)) = undone else { break };
+                steps.push(step);
+                current = previous;
+            }
+            if current == [START] {
+                steps.reverse();
+                return Some(steps);
+            }
+        }
+        None
+    }
+
+    /// A shortest derivation of `molecule` from `e`, by a CYK parse over the
+    /// rules split into binary ones.
+    pub fn shortest_derivation(&self, molecule: &[Atom]) -> Option<Vec<Step>> {
+        Chart::new(self).derive(molecule, START)
+    }
+
+    pub fn fewest_steps(&self, molecule: &[Atom]) -> Option<usize> {
+        self.shortest_derivation(molecule).map(|steps| steps.len())
+    }
+
+    /// The well-known shortcut for inputs whose rules all look like `X => YZ`
+    /// or `X => Y Rn Z (Y Z)* Ar`: tokens, minus `Rn` and `Ar`, minus twice
+    /// the `Y`s, minus one. `None` when the rules do not have that shape.
+    pub fn formula(&self, molecule: &[Atom]) -> Option<usize> {
+        let (rn, ar, y) = (self.index.get("Rn"), self.index.get("Ar"), self.index.get("Y"));
+        let weight = |atoms: &[Atom]| atoms.iter().map(|atom| match Some(atom) {
+            atom if atom == rn || atom == ar => 0,
+            atom if atom == y => -1,
+            _ => 1,
+        }).sum::<i64>();
+        if self.rules.iter().any(|rule| weight(&rule.to) != 2) {
+            return None;
+        }
+        usize::try_from(weight(molecule) - 1).ok()
+    }
+}
+
+/// How a chart entry was reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Back {
+    /// The token itself.
+    Leaf,
+    Unary { rule: usize, body: usize },
+    /// Split after `split` tokens; `rule` is set on the first link of a chain.
+    Binary { split: usize, left: usize, right: usize, rule: Option<usize> },
+}
+
+/// Rules in binary form: every rule of three or more atoms becomes a chain of
+/// two-atom rules through fresh symbols, only the first link costing a step.
+#[derive(Debug, Clone)]
+struct Chart {
+    symbols: usize,
+    /// Heads each pair of symbols can come from, with the rule on first links.
+    binary: HashMap<(usize, usize), Vec<Link>>,
+    unary: Vec<(usize, usize, usize)>,
+}
+
+type Link = (usize, Option<usize>);
+
+/// Symbols deriving one span of the molecule, with their cost and how.
+type Cell = Vec<(usize, u32, Back)>;
+
+impl Chart {
+    fn new(replacements: &Replacements) -> Chart {
+        let mut chart = Chart { symbols: replacements.atoms.len(), binary: HashMap::new(), unary: vec![] };
+        for (idx, rule) in replacements.rules.iter().enumerate() {
+            match rule.to[..] {
+                [only] => chart.unary.push((rule.from, only, idx)),
+                _ => {
+                    let (mut head, mut link) = (rule.from, Some(idx));
+                    for &atom in &rule.to[..rule.to.len() - 2] {
+                        let rest = chart.symbols;
+                        chart.symbols += 1;
+                        chart.binary.entry((atom, rest)).or_default().push((head, link));
+                        (head, link) = (rest, None);
+                    }
+                    let pair = (rule.to[rule.to.len() - 2], rule.to[rule.to.len() - 1]);
+                    chart.binary.entry(pair).or_default().push((head, link));
+                },
+            }
+        }
+        chart
+    }
+
+    /// `cells[start][len - 1]` for every span of `molecule`.
+    fn fill(&self, molecule: &[Atom]) -> Vec<Vec<Cell>> {
+        let n = molecule.len();
+        let mut cells: Vec<Vec<Cell>> = vec![vec![]; n];
+        let mut best = vec![(u32::MAX, Back::Leaf); self.symbols];
+        for len in 1..=n {
+            for start in 0..=n - len {
+                let mut touched = vec![];
+                if len == 1 {
+                    improve(&mut best, &mut touched, molecule[start], (0, Back::Leaf));
+                }
+                for split in 1..len {
+                    for &(left, left_cost, _) in &cells[start][split - 1] {
+                        for &(right, right_cost, _) in &cells[start + split][len - split - 1] {
+                            for &(head, rule) in self.binary.get(&(left, right)).into_iter().flatten() {
+                                let cost = left_cost + right_cost + rule.is_some() as u32;
+                                improve(&mut best, &mut touched, head, (cost, Back::Binary { split, left, right, rule }));
+                            }
+                        }
+                    }
+                }
+                // unary rules may chain, so relax until nothing improves
+                let mut changed = true;
+                while changed {
+                    changed = false;
+                    for &(head, body, rule) in &self.unary {
+                        if best[body].0 != u32::MAX && best[body].0 + 1 < best[head].0 {
+                            let cost = best[body].0 + 1;
+                            improve(&mut best, &mut touched, head, (cost, Back::Unary { rule, body }));
+                            changed = true;
+                        }
+                    }
+                }
+                let cell = touched.iter().map(|&symbol| {
+                    let (cost, back) = std::mem::replace(&mut best[symbol], (u32::MAX, Back::Leaf));
+                    (symbol, cost, back)
+                }).collect();
+                cells[start].push(cell);
+            }
+        }
+        cells
+    }
+
+    /// Cheapest derivation of `molecule` from `goal`, leftmost first.
+    fn derive(&self, molecule: &[Atom], goal: usize) -> Option<Vec<Step>> {
+        if molecule.is_empty() {
+            return None;
+        }
+        let cells = self.fill(molecule);
+        let mut steps = vec![];
+        trace(&cells, goal, 0, molecule.len(), &mut steps)?;
+        Some(steps)
+    }
+}
+
+/// Lowers the cost of `symbol` in the cell being filled, noting it the first time.
+fn improve(best: &mut [(u32, Back)], touched: &mut Vec<usize>, symbol: usize, entry: (u32, Back)) {
+    if entry.0 < best[symbol].0 {
+        if best[symbol].0 == u32::MAX {
+            touched.push(symbol);
+        }
+        best[symbol] = entry;
+    }
+}
+
+/// Steps turning `symbol` into the span, expanding the left part first so
+/// that every step lands where its span starts.
+fn trace(cells: &[Vec<Cell>], symbol: usize, start: usize, len: usize, steps: &mut Vec<Step>) -> Option<()> {
+    let &(_, _, back) = cells[start][len - 1].iter().find(|entry| entry.0 == symbol)?;
+    match back {
+        Back::Leaf => (),
+        Back::Unary { rule, body } => {
+            steps.push(Step { position: start, rule });
+            trace(cells, body, start, len, steps)?;
+        },
+        Back::Binary { split, left, right, rule } => {
+            if let Some(rule) = rule {
+                steps.push(Step { position: start, rule });
+            }
+            trace(cells, left, start, split, steps)?;
+            trace(cells, right, start + split, len - split, steps)?;
+        },
+    }
+    Some(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOHOHO: &str = "e => H\ne => O\nH => HO\nH => OH\nO => HH\n\nHOHOHO\n";
+
+    #[test]
+    fn test_steps() {
+        let (replacements, molecule) = parse(HOHOHO).unwrap();
+        assert_eq!(replacements.successors(&molecule).len(), 7);
+        assert_eq!(replacements.fewest_steps(&molecule), Some(6));
+        assert_eq!(replacements.formula(&molecule), None);
+
+        let steps = replacements.reduce_greedy(&molecule, 10, 1).unwrap();
+        let built = steps.iter().try_fold(vec![START], |current, &step| replacements.apply(&current, step));
+        assert_eq!(built, Some(molecule.clone()));
+        assert_eq!(replacements.undo(&molecule, Step { position: 0, rule: 0 }), None);
+        assert!(parse("H => HO\n").is_err());
+
+        let (mut replacements, molecule) = parse(HOHOHO).unwrap();
+        let shortest = replacements.shortest_derivation(&molecule).unwrap();
+        let molecules = replacements.molecules(&shortest).unwrap();
+        assert_eq!(molecules.last(), Some(&molecule));
+        assert_eq!(replacements.describe(shortest[0]), "e => H at 0");
+        let written = molecules.iter().map(|m| replacements.render(m) + "  # note").collect::<Vec<_>>();
+        // different steps can lead to the same molecule, so compare molecules
+        let checked = replacements.check_derivation(&written.join("\n"), &molecule).unwrap();
+        assert_eq!(replacements.molecules(&checked).as_ref(), Some(&molecules));
+        let backwards = written.iter().rev().cloned().collect::<Vec<_>>().join("\n\n");
+        let checked = replacements.check_derivation(&backwards, &molecule).unwrap();
+        assert_eq!(replacements.molecules(&checked), Some(molecules));
+        assert_eq!(replacements.check_derivation("e\nH\nHOO\nHOHOHO", &molecule), Err(DerivationError::NotOneStep { line: 3 }));
+        assert_eq!(replacements.check_derivation("e\nH\nHO", &molecule), Err(DerivationError::Ends));
+        assert_eq!(replacements.check_derivation("e\nh", &molecule), Err(DerivationError::Parse { line: 2, text: "h".to_string() }));
+        assert_eq!(parse("H => ho\n\nH").unwrap_err(), MoleculeError::Parse { line: 1, text: "H => ho".to_string() });
+    }
+
+    #[test]
+    fn test_formula() {
+        // every derivation under rules of this shape takes as many steps as the formula says
+        let rules = "e => HF\ne => NAl\nH => CRnAlAr\nH => NTh\nH => CRnFYFYFAr\nF => CaF\nCa => CaCa\nAl => ThF\nN => CRnFAr\nTh => ThCa\n";
+        let (replacements, _) = parse(&format!("{rules}\ne\n")).unwrap();
+        let mut rng = XorShift(42);
+        for length in 1..=12 {
+            let mut molecule = vec![START];
+            for _ in 0..length {
+                let steps = (0..molecule.len())
+                    .flat_map(|position| (0..replacements.rules.len()).map(move |rule| Step { position, rule }))
+                    .filter(|&step| replacements.apply(&molecule, step).is_some())
+                    .collect::<Vec<_>>();
+                molecule = replacements.apply(&molecule, steps[(rng.next() % steps.len() as u64) as usize]).unwrap();
+            }
+            let target = replacements.render(&molecule);
+            assert_eq!(replacements.formula(&molecule), Some(length), "{target}");
+            let shortest = replacements.shortest_derivation(&molecule).unwrap();
+            assert_eq!(shortest.len(), length, "{target}");
+            assert_eq!(replacements.molecules(&shortest).unwrap().last(), Some(&molecule), "{target}");
+            assert_eq!(replacements.reduce_greedy(&molecule, 100, 7).map(|steps| steps.len()), Some(length), "{target}");
+        }
+    }
+}
@@ -0,0 +1,228 @@
+/*
+ * Password policies built from small rules, with a search for the next
+ * password that satisfies all of them.
+ * Example: `Policy::santa().next_after("abcdefgh")`.
+ */
+use std::fmt::Display;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// `len` letters going up by one in a row, like `abc`.
+    Straight(usize),
+    /// None of these letters.
+    Forbidden(Vec<u8>),
+    /// At least `count` non-overlapping pairs like `aa`; `distinct` asks for
+    /// pairs of different letters.
+    Pairs { count: usize, distinct: bool },
+    /// Exactly this many letters.
+    Length(usize),
+    /// Only these letters.
+    Alphabet(Vec<u8>),
+}
+
+/// Length of the run of letters going up by one that ends the password.
+fn trailing_straight(password: &[u8]) -> usize {
+    let Some(mut last) = password.last().copied() else { return 0 };
+    let mut len = 1;
+    for &letter in password.iter().rev().skip(1) {
+        if letter + 1 != last {
+            break;
+        }
+        (last, len) = (letter, len + 1);
+    }
+    len
+}
+
+/// Pairs found so far, and whether the last letter is free to start another one.
+fn pairs(password: &[u8], distinct: bool) -> (usize, bool) {
+    let (mut found, mut letters, mut idx) = (0, vec![], 1);
+    while idx < password.len() {
+        if password[idx - 1] == password[idx] && !(distinct && letters.contains(&password[idx])) {
+            letters.push(password[idx]);
+            found += 1;
+            idx += 2;
+        } else {
+            idx += 1;
+        }
+    }
+    (found, idx == password.len())
+}
+
+impl Rule {
+    /// Whether `letter` may appear anywhere in a password.
+    pub fn allows(&self, letter: u8) -> bool {
+        match self {
+            Rule::Forbidden(letters) => !letters.contains(&letter),
+            Rule::Alphabet(letters) => letters.contains(&letter),
+            _ => true,
+        }
+    }
+
+    /// Whether `prefix` may still become a valid password with `remaining`
+    /// more letters. Never rules out a prefix that can be completed, and is
+    /// exact once nothing remains.
+    pub fn feasible(&self, prefix: &[u8], remaining: usize) -> bool {
+        match self {
+            Rule::Straight(len) => {
+                *len == 0
+                    || prefix.windows(*len).any(|run| run.windows(2).all(|pair| pair[0] + 1 == pair[1]))
+                    || remaining >= len.saturating_sub(trailing_straight(prefix))
+            },
+            Rule::Forbidden(_) | Rule::Alphabet(_) => prefix.iter().all(|&letter| self.allows(letter)),
+            Rule::Pairs { count, distinct } => {
+                let (found, open) = pairs(prefix, *distinct);
+                found + (remaining + open as usize) / 2 >= *count
+            },
+            Rule::Length(len) => prefix.len() + remaining == *len,
+        }
+    }
+
+    pub fn holds(&self, password: &[u8]) -> bool {
+        self.feasible(password, 0)
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let letters = |letters: &[u8]| String::from_utf8_lossy(letters).into_owned();
+        match self {
+            Rule::Straight(len) => write!(f, "a straight of {len} letters"),
+            Rule::Forbidden(forbidden) => write!(f, "none of `{}`", letters(forbidden)),
+            Rule::Pairs { count, distinct: false } => write!(f, "{count} non-overlapping pairs"),
+            Rule::Pairs { count, distinct: true } => write!(f, "{count} pairs of different letters"),
+            Rule::Length(len) => write!(f, "exactly {len} letters"),
+            Rule::Alphabet(allowed) => write!(f, "only `{}`", letters(allowed)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Policy {
+    pub rules: Vec<Rule>,
+}
+
+impl Policy {
+    pub fn new(rules: Vec<Rule>) -> Policy {
+        Policy { rules }
+    }
+
+    /// Santa's 2015 day 11 policy.
+    pub fn santa() -> Policy {
+        Policy::new(vec![
+            Rule::Length(8),
+            Rule::Straight(3),
+            Rule::Forbidden(b"iol".to_vec()),
+            Rule::Pairs { count: 2, distinct: false },
+        ])
+    }
+
+    /// Rules `password` breaks.
+    pub fn violations(&self, password: &str) -> Vec<&Rule> {
+        self.rules.iter().filter(|rule| !rule.holds(password.as_bytes())).collect()
+    }
+
+    pub fn is_valid(&self, password: &str) -> bool {
+        self.violations(password).is_empty()
+    }
+
+    /// Letters to count with, `a` to `z` minus any a rule bans.
+    fn letters(&self) -> Vec<u8> {
+        (b'a'..=b'z').filter(|&letter| self.rules.iter().all(|rule| rule.allows(letter))).collect()
+    }
+
+    fn feasible(&self, prefix: &[u8], remaining: usize) -> bool {
+        self.rules.iter().all(|rule| rule.feasible(prefix, remaining))
+    }
+
+    /// Smallest valid password of length `len` starting with `prefix`, skipping
+    /// every prefix that can no longer be completed.
+    fn complete(&self, prefix: &mut Vec<u8>, len: usize, letters: &[u8]) -> bool {
+        if !self.feasible(prefix, len - prefix.len()) {
+            return false;
+        }
+        if prefix.len() == len {
+            return true;
+        }
+        for &letter in letters {
+            prefix.push(letter);
+            if self.complete(prefix, len, letters) {
+                return true;
+            }
+            prefix.pop();
+        }
+        false
+    }
+
+    /// The first valid password after `password` of the same length, counting
+    /// with letters as digits; `None` once past `zz...z`.
+    pub fn next_after(&self, password: &str) -> Option<String> {
+        let current = password.as_bytes();
+        let letters = self.letters();
+        for idx in (0..current.len()).rev() {
+            for &letter in letters.iter().filter(|&&letter| letter > current[idx]) {
+                let mut prefix = current[..idx].to_vec();
+                prefix.push(letter);
+                if self.complete(&mut prefix, current.len(), &letters) {
+                    return Some(String::from_utf8(prefix).unwrap());
+                }
+            }
+        }
+        None
+    }
+
+    /// `password` itself when valid, otherwise the next valid one.
+    pub fn first_from(&self, password: &str) -> Option<String> {
+        match self.is_valid(password) {
+            true => Some(password.to_string()),
+            false => self.next_after(password),
+        }
+    }
+
+    /// `count` passwords in a row after `password`.
+    pub fn chain(&self, password: &str, count: usize) -> Vec<String> {
+        let mut chain: Vec<String> = vec![];
+        while chain.len() < count {
+            let Some(next) = self.next_after(chain.last().map_or(password, |last| last)) else { break };
+            chain.push(next);
+        }
+        chain
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() {
+        let santa = Policy::santa();
+        assert_eq!(santa.violations("hijklmmn"), [&Rule::Forbidden(b"iol".to_vec()), &Rule::Pairs { count: 2, distinct: false }]);
+        assert_eq!(santa.violations("abbceffg"), [&Rule::Straight(3)]);
+        assert!(santa.is_valid("abcdffaa"));
+        assert!(santa.is_valid("xyzaaaab"));
+        assert!(!Rule::Pairs { count: 2, distinct: true }.holds(b"xyzaaaab"));
+        assert_eq!(Rule::Pairs { count: 2, distinct: true }.to_string(), "2 pairs of different letters");
+    }
+
+    #[test]
+    fn test_next() {
+        let santa = Policy::santa();
+        assert_eq!(santa.next_after("abcdefgh").as_deref(), Some("abcdffaa"));
+        assert_eq!(santa.next_after("ghijklmn").as_deref(), Some("ghjaabcc"));
+        assert_eq!(santa.next_after("zzzzzzzz"), None);
+
+        // the search agrees with plain counting
+        let policy = Policy::new(vec![Rule::Straight(2), Rule::Pairs { count: 1, distinct: false }, Rule::Alphabet(b"abcd".to_vec())]);
+        let mut password = b"aaaa".to_vec();
+        let mut expected = vec![];
+        while let Some(idx) = password.iter().rposition(|&letter| letter < b'd') {
+            password[idx] += 1;
+            password[idx + 1..].iter_mut().for_each(|letter| *letter = b'a');
+            let text = String::from_utf8(password.clone()).unwrap();
+            if policy.is_valid(&text) {
+                expected.push(text);
+            }
+        }
+        assert_eq!(policy.chain("aaaa", 1000), expected);
+    }
+}
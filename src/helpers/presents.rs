@@ -0,0 +1,146 @@
+/*
+ * Elves delivering presents: elf `n` visits houses n, 2n, 3n, ... leaving
+ * `multiplier * n` presents, maybe stopping after a number of houses.
+ * Presents per house come from a sieve, grown until it reaches the target.
+ * Example: `Delivery::new(10).first_house(36_000_000)`.
+ */
+use std::fmt::Display;
+
+/// Houses sieved in the first round of `first_house`.
+const FIRST_SIEVE: u64 = 1 << 10;
+
+/// Most houses `sieve` counts at once, 128 MiB worth.
+pub const MAX_HOUSES: u64 = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresentsError {
+    /// A sieve over this many houses, more than `MAX_HOUSES`.
+    TooLarge(u64),
+    /// Some house gets more than `u64::MAX` presents.
+    Overflow,
+}
+
+impl Display for PresentsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PresentsError::TooLarge(houses) => write!(f, "a sieve over {houses} houses is over the limit of {MAX_HOUSES}"),
+            PresentsError::Overflow => write!(f, "too many presents to count in 64 bits"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Delivery {
+    pub multiplier: u64,
+    /// Houses each elf visits before stopping, if they ever do.
+    pub limit: Option<u64>,
+}
+
+impl Delivery {
+    pub fn new(multiplier: u64) -> Delivery {
+        Delivery { multiplier, limit: None }
+    }
+
+    pub fn limit(mut self, houses: u64) -> Delivery {
+        self.limit = Some(houses);
+        self
+    }
+
+    /// Whether elf `elf` still goes to `house`, which it visits anyway.
+    fn visits(&self, elf: u64, house: u64) -> bool {
+        self.limit.is_none_or(|limit| house / elf <= limit)
+    }
+
+    /// Presents left at `house`, from its divisors.
+    pub fn presents(&self, house: u64) -> Result<u64, PresentsError> {
+        let mut total = 0u64;
+        let mut elf = 1;
+        while elf <= house / elf {
+            if house.is_multiple_of(elf) {
+                let pair = house / elf;
+                if self.visits(elf, house) {
+                    total = total.checked_add(elf).ok_or(PresentsError::Overflow)?;
+                }
+                if pair != elf && self.visits(pair, house) {
+                    total = total.checked_add(pair).ok_or(PresentsError::Overflow)?;
+                }
+            }
+            elf += 1;
+        }
+        total.checked_mul(self.multiplier).ok_or(PresentsError::Overflow)
+    }
+
+    /// Presents at every house up to `houses`; index 0 is unused. Fails over
+    /// `MAX_HOUSES` houses or past `u64::MAX` presents.
+    pub fn sieve(&self, houses: u64) -> Result<Vec<u64>, PresentsError> {
+        if houses > MAX_HOUSES {
+            return Err(PresentsError::TooLarge(houses));
+        }
+        let mut presents = vec![0u64; houses as usize + 1];
+        for elf in 1..=houses {
+            let visited = self.limit.map_or(usize::MAX, |limit| limit as usize);
+            let gift = elf.checked_mul(self.multiplier).ok_or(PresentsError::Overflow)?;
+            for house in (elf..=houses).step_by(elf as usize).take(visited) {
+                let count = &mut presents[house as usize];
+                *count = count.checked_add(gift).ok_or(PresentsError::Overflow)?;
+            }
+        }
+        Ok(presents)
+    }
+
+    /// The first house with at least `target` presents. Elf `n` always gets
+    /// to house `n`, so house `target / multiplier` (rounded up) is far enough;
+    /// the sieve doubles from a small size until it finds one.
+    pub fn first_house(&self, target: u64) -> Result<Option<u64>, PresentsError> {
+        if self.multiplier == 0 {
+            return Ok((target == 0).then_some(1));
+        }
+        let bound = target.div_ceil(self.multiplier).max(1);
+        let mut houses = FIRST_SIEVE.min(bound);
+        loop {
+            let presents = self.sieve(houses)?;
+            if let Some(house) = (1..presents.len()).find(|&house| presents[house] >= target) {
+                return Ok(Some(house as u64));
+            }
+            if houses == bound {
+                return Ok(None);
+            }
+            houses = houses.saturating_mul(2).min(bound);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_presents() {
+        let endless = Delivery::new(10);
+        assert_eq!((1..=9).map(|house| endless.presents(house).unwrap()).collect::<Vec<_>>(), [10, 30, 40, 70, 60, 120, 80, 150, 130]);
+        let lazy = Delivery::new(11).limit(50);
+        let sieved = lazy.sieve(500).unwrap();
+        assert!((1..=500).all(|house| Ok(sieved[house as usize]) == lazy.presents(house)));
+        assert_eq!(lazy.presents(51), Ok(11 * (3 + 17 + 51)));
+        let greedy = Delivery::new(u64::MAX / 2);
+        assert_eq!((greedy.presents(1), greedy.presents(2)), (Ok(u64::MAX / 2), Err(PresentsError::Overflow)));
+        assert_eq!(greedy.sieve(2), Err(PresentsError::Overflow));
+        assert_eq!(lazy.sieve(MAX_HOUSES + 1), Err(PresentsError::TooLarge(MAX_HOUSES + 1)));
+    }
+
+    #[test]
+    fn test_first_house() {
+        assert_eq!(Delivery::new(10).first_house(150), Ok(Some(8)));
+        assert_eq!(Delivery::new(11).limit(50).first_house(130), Ok(Some(6)));
+        // past the first round of the sieve, it agrees with going house by house
+        for delivery in [Delivery::new(10), Delivery::new(11).limit(50), Delivery::new(7).limit(3)] {
+            for target in [29_000, 100_000] {
+                let expected = (1..).find(|&house| delivery.presents(house).unwrap() >= target);
+                assert_eq!(delivery.first_house(target), Ok(expected), "{delivery:?} {target}");
+            }
+        }
+        // with one house each, house n only ever gets elf n's presents
+        assert_eq!(Delivery::new(1).limit(1).first_house(5000), Ok(Some(5000)));
+        assert_eq!(Delivery::new(0).first_house(1), Ok(None));
+    }
+}
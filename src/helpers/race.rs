@@ -0,0 +1,182 @@
+/*
+ * Reindeer races: closed-form distances, the per-second leader and points
+ * timeline, and a CSV of it for plotting.
+ * Example: `Race::parse(input, 2503)?.winning_distance()`.
+ */
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Display;
+
+lazy_static! {
+    static ref REINDEER_REGEX: Regex = Regex::new(
+        r"^(?<name>\w+) can fly (?<speed>\d+) km/s for (?<fly>\d+) seconds, but then must rest for (?<rest>\d+) seconds\.$"
+    ).unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RaceError {
+    Parse { line: usize, text: String },
+    Overflow { name: String, seconds: u32 },
+}
+
+impl Display for RaceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RaceError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            RaceError::Overflow { name, seconds } => write!(f, "{name}'s distance after {seconds}s does not fit in 64 bits"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reindeer {
+    pub name: String,
+    /// km per second while flying.
+    pub speed: u32,
+    pub fly: u32,
+    pub rest: u32,
+}
+
+impl Reindeer {
+    pub fn parse(line: &str) -> Option<Reindeer> {
+        let captured = REINDEER_REGEX.captures(line.trim())?;
+        Some(Reindeer {
+            name: captured["name"].to_string(),
+            speed: captured["speed"].parse().ok()?,
+            fly: captured["fly"].parse().ok()?,
+            rest: captured["rest"].parse().ok()?,
+        })
+    }
+
+    /// Kilometres covered after `seconds`: whole fly/rest cycles, then
+    /// whatever part of a flight fits in the rest.
+    pub fn distance_at(&self, seconds: u32) -> Result<u64, RaceError> {
+        let overflow = || RaceError::Overflow { name: self.name.clone(), seconds };
+        let (seconds, fly) = (u64::from(seconds), u64::from(self.fly));
+        let cycle = fly.checked_add(u64::from(self.rest)).ok_or_else(overflow)?;
+        if cycle == 0 {
+            return Ok(0);
+        }
+        let flying = (seconds / cycle).checked_mul(fly)
+            .and_then(|flown| flown.checked_add((seconds % cycle).min(fly)))
+            .ok_or_else(overflow)?;
+        flying.checked_mul(u64::from(self.speed)).ok_or_else(overflow)
+    }
+}
+
+/// Standings at the end of one second.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tick {
+    pub second: u32,
+    pub distances: Vec<u64>,
+    /// Points so far, this second's included.
+    pub points: Vec<u32>,
+    /// Everyone tied for the lead this second.
+    pub leaders: Vec<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Race {
+    pub herd: Vec<Reindeer>,
+    pub duration: u32,
+}
+
+impl Race {
+    pub fn new(herd: Vec<Reindeer>, duration: u32) -> Race {
+        Race { herd, duration }
+    }
+
+    /// One reindeer per non-blank line.
+    pub fn parse(input: &str, duration: u32) -> Result<Race, RaceError> {
+        let herd = input.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| Reindeer::parse(line).ok_or_else(|| RaceError::Parse { line: idx + 1, text: line.to_string() }))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Race::new(herd, duration))
+    }
+
+    /// Distance of the reindeer furthest ahead at the end.
+    pub fn winning_distance(&self) -> Result<u64, RaceError> {
+        Ok(self.distances_at(self.duration)?.into_iter().max().unwrap_or(0))
+    }
+
+    fn distances_at(&self, second: u32) -> Result<Vec<u64>, RaceError> {
+        self.herd.iter().map(|reindeer| reindeer.distance_at(second)).collect()
+    }
+
+    /// Everyone tied for the lead at `second`, and every distance then.
+    fn standings(&self, second: u32) -> Result<(Vec<u64>, Vec<usize>), RaceError> {
+        let distances = self.distances_at(second)?;
+        let best = distances.iter().copied().max().unwrap_or(0);
+        let leaders = (0..distances.len()).filter(|&idx| distances[idx] == best).collect();
+        Ok((distances, leaders))
+    }
+
+    /// Every second from 1 to the duration; each second the leaders get a point.
+    pub fn timeline(&self) -> Result<Vec<Tick>, RaceError> {
+        let mut points = vec![0; self.herd.len()];
+        let mut timeline = vec![];
+        for second in 1..=self.duration {
+            let (distances, leaders) = self.standings(second)?;
+            leaders.iter().for_each(|&idx| points[idx] += 1);
+            timeline.push(Tick { second, distances, points: points.clone(), leaders });
+        }
+        Ok(timeline)
+    }
+
+    /// Points of every reindeer at the end, without keeping the timeline.
+    pub fn points(&self) -> Result<Vec<u32>, RaceError> {
+        let mut points = vec![0; self.herd.len()];
+        for second in 1..=self.duration {
+            self.standings(second)?.1.into_iter().for_each(|idx| points[idx] += 1);
+        }
+        Ok(points)
+    }
+
+    /// One row per second: the distance and points of every reindeer, then the leaders.
+    pub fn to_csv(&self) -> Result<String, RaceError> {
+        let mut header = vec!["second".to_string()];
+        header.extend(self.herd.iter().map(|reindeer| format!("{} km", reindeer.name)));
+        header.extend(self.herd.iter().map(|reindeer| format!("{} points", reindeer.name)));
+        header.push("leaders".to_string());
+        let mut out = header.join(",") + "\n";
+        for tick in self.timeline()? {
+            let mut row = vec![tick.second.to_string()];
+            row.extend(tick.distances.iter().map(|distance| distance.to_string()));
+            row.extend(tick.points.iter().map(|points| points.to_string()));
+            row.push(tick.leaders.iter().map(|&idx| self.herd[idx].name.as_str()).collect::<Vec<_>>().join(";"));
+            out += &(row.join(",") + "\n");
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HERD: &str = "Comet can fly 14 km/s for 10 seconds, but then must rest for 127 seconds.\n\
+        Dancer can fly 16 km/s for 11 seconds, but then must rest for 162 seconds.\n";
+
+    #[test]
+    fn test_distance() {
+        let race = Race::parse(HERD, 1000).unwrap();
+        let comet = &race.herd[0];
+        assert_eq!([1, 10, 11, 137, 138, 1000].map(|t| comet.distance_at(t).unwrap()), [14, 140, 140, 140, 154, 1120]);
+        assert_eq!(race.winning_distance(), Ok(1120));
+        let rocket = Reindeer { name: "Rocket".to_string(), speed: u32::MAX, fly: u32::MAX, rest: u32::MAX };
+        assert_eq!(rocket.distance_at(u32::MAX), Ok(u64::from(u32::MAX) * u64::from(u32::MAX)));
+        assert_eq!(Race::parse("Comet flies", 1), Err(RaceError::Parse { line: 1, text: "Comet flies".to_string() }));
+    }
+
+    #[test]
+    fn test_timeline() {
+        let race = Race::parse(HERD, 1000).unwrap();
+        let timeline = race.timeline().unwrap();
+        assert_eq!(timeline[0].leaders, [1]);
+        assert_eq!(timeline[139].points, [1, 139]);
+        assert_eq!(race.points(), Ok(vec![312, 689]));
+        let csv = Race::parse(HERD, 2).unwrap().to_csv();
+        assert_eq!(csv.as_deref(), Ok("second,Comet km,Dancer km,Comet points,Dancer points,leaders\n1,14,16,0,1,Dancer\n2,28,32,0,2,Dancer\n"));
+    }
+}
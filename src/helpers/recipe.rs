@@ -0,0 +1,273 @@
+/*
+ * Recipes: split a fixed number of teaspoons between ingredients to maximise
+ * the product of some property totals, optionally with exact totals for
+ * others, by a branch-and-bound search that never lists the recipe space.
+ * Example: `Optimizer::new(&pantry).target("calories", 500)?.best()`.
+ */
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Display;
+
+lazy_static! {
+    static ref INGREDIENT_REGEX: Regex = Regex::new(r"^(?<name>\w+): (?<properties>.+)$").unwrap();
+    static ref PROPERTY_REGEX: Regex = Regex::new(r"^(?<property>\w+) (?<amount>-?\d+)$").unwrap();
+}
+
+/// Properties left out of the score unless asked for.
+const UNSCORED: [&str; 1] = ["calories"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecipeError {
+    Parse { line: usize, text: String },
+    UnknownProperty(String),
+}
+
+impl Display for RecipeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RecipeError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            RecipeError::UnknownProperty(name) => write!(f, "no ingredient has a property `{name}`"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ingredient {
+    pub name: String,
+    /// Per teaspoon, in the order of `Pantry::properties`.
+    pub amounts: Vec<i64>,
+}
+
+/// Ingredients and every property any of them lists; a property an
+/// ingredient does not list counts as 0 for it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pantry {
+    pub properties: Vec<String>,
+    pub ingredients: Vec<Ingredient>,
+}
+
+impl Pantry {
+    /// One `Name: property n, property n, ...` per non-blank line.
+    pub fn parse(input: &str) -> Result<Pantry, RecipeError> {
+        let mut pantry = Pantry::default();
+        for (idx, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let error = || RecipeError::Parse { line: idx + 1, text: line.to_string() };
+            let captured = INGREDIENT_REGEX.captures(line.trim()).ok_or_else(error)?;
+            let mut amounts = vec![0; pantry.properties.len()];
+            for property in captured["properties"].split(',') {
+                let captured = PROPERTY_REGEX.captures(property.trim()).ok_or_else(error)?;
+                let id = pantry.add_property(&captured["property"]);
+                amounts.resize(pantry.properties.len(), 0);
+                amounts[id] = captured["amount"].parse().map_err(|_| error())?;
+            }
+            pantry.ingredients.push(Ingredient { name: captured["name"].to_string(), amounts });
+        }
+        pantry.ingredients.iter_mut().for_each(|ingredient| ingredient.amounts.resize(pantry.properties.len(), 0));
+        Ok(pantry)
+    }
+
+    fn add_property(&mut self, name: &str) -> usize {
+        self.property(name).unwrap_or_else(|| {
+            self.properties.push(name.to_string());
+            self.properties.len() - 1
+        })
+    }
+
+    pub fn property(&self, name: &str) -> Option<usize> {
+        self.properties.iter().position(|property| property == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipe {
+    /// Teaspoons of each ingredient, in pantry order.
+    pub teaspoons: Vec<u32>,
+    /// Total of each property, in pantry order.
+    pub totals: Vec<i64>,
+    pub score: i64,
+}
+
+impl Recipe {
+    /// `44 tsp Butterscotch, 56 tsp Cinnamon`, leaving out unused ingredients.
+    pub fn describe(&self, pantry: &Pantry) -> String {
+        self.teaspoons.iter().zip(&pantry.ingredients)
+            .filter(|(&teaspoons, _)| teaspoons > 0)
+            .map(|(teaspoons, ingredient)| format!("{teaspoons} tsp {}", ingredient.name))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Product of the scored totals, negative totals counting as 0.
+fn score(totals: &[i64], scored: &[usize]) -> i64 {
+    scored.iter().fold(1, |acc: i64, &property| acc.saturating_mul(totals[property].max(0)))
+}
+
+#[derive(Debug, Clone)]
+pub struct Optimizer<'a> {
+    pantry: &'a Pantry,
+    teaspoons: u32,
+    scored: Vec<usize>,
+    targets: Vec<(usize, i64)>,
+}
+
+impl<'a> Optimizer<'a> {
+    /// 100 teaspoons, scoring every property but calories.
+    pub fn new(pantry: &'a Pantry) -> Optimizer<'a> {
+        let scored = (0..pantry.properties.len()).filter(|&id| !UNSCORED.contains(&pantry.properties[id].as_str())).collect();
+        Optimizer { pantry, teaspoons: 100, scored, targets: vec![] }
+    }
+
+    pub fn teaspoons(mut self, teaspoons: u32) -> Optimizer<'a> {
+        self.teaspoons = teaspoons;
+        self
+    }
+
+    /// Scores these properties instead.
+    pub fn score(mut self, properties: &[&str]) -> Result<Optimizer<'a>, RecipeError> {
+        self.scored = properties.iter().map(|name| self.resolve(name)).collect::<Result<_, _>>()?;
+        Ok(self)
+    }
+
+    /// Only recipes whose `property` adds up to exactly `total`.
+    pub fn target(mut self, property: &str, total: i64) -> Result<Optimizer<'a>, RecipeError> {
+        let id = self.resolve(property)?;
+        self.targets.push((id, total));
+        Ok(self)
+    }
+
+    fn resolve(&self, name: &str) -> Result<usize, RecipeError> {
+        self.pantry.property(name).ok_or_else(|| RecipeError::UnknownProperty(name.to_string()))
+    }
+
+    /// The highest scoring recipe, the first found on ties; `None` when no
+    /// recipe meets the targets.
+    pub fn best(&self) -> Option<Recipe> {
+        let ingredients = &self.pantry.ingredients;
+        if ingredients.is_empty() {
+            return None;
+        }
+        // lowest and highest amount per teaspoon of every property among the
+        // ingredients from each index on
+        let mut reach = vec![vec![(i64::MAX, i64::MIN); self.pantry.properties.len()]; ingredients.len()];
+        for idx in (0..ingredients.len()).rev() {
+            for (property, &amount) in ingredients[idx].amounts.iter().enumerate() {
+                let (low, high) = reach.get(idx + 1).map_or((amount, amount), |next| next[property]);
+                reach[idx][property] = (low.min(amount), high.max(amount));
+            }
+        }
+        let mut search = Search {
+            optimizer: self,
+            reach,
+            teaspoons: vec![0; ingredients.len()],
+            totals: vec![0; self.pantry.properties.len()],
+            best: None,
+        };
+        search.place(0, self.teaspoons);
+        search.best
+    }
+}
+
+struct Search<'a, 'b> {
+    optimizer: &'b Optimizer<'a>,
+    reach: Vec<Vec<(i64, i64)>>,
+    teaspoons: Vec<u32>,
+    totals: Vec<i64>,
+    best: Option<Recipe>,
+}
+
+impl Search<'_, '_> {
+    fn add(&mut self, idx: usize, teaspoons: u32, sign: i64) {
+        self.teaspoons[idx] = if sign > 0 { teaspoons } else { 0 };
+        let amounts = &self.optimizer.pantry.ingredients[idx].amounts;
+        for (total, amount) in self.totals.iter_mut().zip(amounts) {
+            *total += sign * amount * teaspoons as i64;
+        }
+    }
+
+    /// Whether `left` teaspoons of the ingredients from `idx` on can still
+    /// meet every target and beat the best recipe so far.
+    fn promising(&self, idx: usize, left: u32) -> bool {
+        let left = left as i64;
+        let reach = &self.reach[idx];
+        let reachable = self.optimizer.targets.iter().all(|&(property, total)| {
+            let (low, high) = reach[property];
+            (self.totals[property] + low * left..=self.totals[property] + high * left).contains(&total)
+        });
+        let bound = self.optimizer.scored.iter()
+            .fold(1, |acc: i64, &property| acc.saturating_mul((self.totals[property] + reach[property].1 * left).max(0)));
+        reachable && self.best.as_ref().is_none_or(|best| bound > best.score)
+    }
+
+    /// Tries every split of `left` teaspoons between the ingredients from
+    /// `idx` on; the last one takes whatever is left.
+    fn place(&mut self, idx: usize, left: u32) {
+        if !self.promising(idx, left) {
+            return;
+        }
+        if idx + 1 == self.teaspoons.len() {
+            self.add(idx, left, 1);
+            let meets = self.optimizer.targets.iter().all(|&(property, total)| self.totals[property] == total);
+            let score = score(&self.totals, &self.optimizer.scored);
+            if meets && self.best.as_ref().is_none_or(|best| score > best.score) {
+                self.best = Some(Recipe { teaspoons: self.teaspoons.clone(), totals: self.totals.clone(), score });
+            }
+            self.add(idx, left, -1);
+            return;
+        }
+        for teaspoons in 0..=left {
+            self.add(idx, teaspoons, 1);
+            self.place(idx + 1, left - teaspoons);
+            self.add(idx, teaspoons, -1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PANTRY: &str = "Butterscotch: capacity -1, durability -2, flavor 6, texture 3, calories 8\n\
+        Cinnamon: capacity 2, durability 3, flavor -2, texture -1, calories 3\n";
+
+    #[test]
+    fn test_best() {
+        let pantry = Pantry::parse(PANTRY).unwrap();
+        let best = Optimizer::new(&pantry).best().unwrap();
+        assert_eq!((best.teaspoons.as_slice(), best.score), (&[44, 56][..], 62842880));
+        assert_eq!(best.describe(&pantry), "44 tsp Butterscotch, 56 tsp Cinnamon");
+        let lean = Optimizer::new(&pantry).target("calories", 500).unwrap().best().unwrap();
+        assert_eq!((lean.teaspoons.as_slice(), lean.score, lean.totals[4]), (&[40, 60][..], 57600000, 500));
+        assert!(Optimizer::new(&pantry).target("calories", 1).unwrap().best().is_none());
+        assert_eq!(Optimizer::new(&pantry).score(&["sugar"]).unwrap_err(), RecipeError::UnknownProperty("sugar".to_string()));
+        assert_eq!(Pantry::parse("Salt: lots"), Err(RecipeError::Parse { line: 1, text: "Salt: lots".to_string() }));
+    }
+
+    #[test]
+    fn test_exhaustive() {
+        // the pruned search scores the same as trying every split
+        let pantry = Pantry::parse("A: x 3, y -1, z 2\nB: x -2, y 4\nC: y 1, z -1, x 1\nD: z 5, x -1, y -2\n").unwrap();
+        for target in [None, Some(12), Some(20)] {
+            let mut optimizer = Optimizer::new(&pantry).teaspoons(20);
+            if let Some(total) = target {
+                optimizer = optimizer.target("z", total).unwrap();
+            }
+            let mut expected = None;
+            for a in 0..=20 {
+                for b in 0..=20 - a {
+                    for c in 0..=20 - a - b {
+                        let teaspoons = [a, b, c, 20 - a - b - c];
+                        let totals = (0..3).map(|property| {
+                            (0..4).map(|idx| pantry.ingredients[idx].amounts[property] * teaspoons[idx]).sum::<i64>()
+                        }).collect::<Vec<_>>();
+                        if target.is_some_and(|total| totals[2] != total) {
+                            continue;
+                        }
+                        expected = expected.max(Some(score(&totals, &[0, 1, 2])));
+                    }
+                }
+            }
+            assert_eq!(optimizer.best().map(|recipe| recipe.score), expected, "z = {target:?}");
+        }
+    }
+}
@@ -0,0 +1,222 @@
+/*
+ * Matching records with partial property lists against a known readout,
+ * comparing each property its own way and ranking every record by how many
+ * of its properties fit.
+ * Example: `Matcher::new(Record::parse_readout(text)?).compare("cats", Comparator::Gt).rank(&records)`.
+ */
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MatchError {
+    Parse { line: usize, text: String },
+    Comparator(String),
+}
+
+impl Display for MatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MatchError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            MatchError::Comparator(text) => write!(f, "cannot parse comparator `{text}`, expected eq, gt, lt or LOW..=HIGH"),
+        }
+    }
+}
+
+/// `key: value, key: value, ...`
+fn parse_properties(text: &str) -> Option<Vec<(String, i64)>> {
+    text.split(',').map(|property| {
+        let (key, value) = property.split_once(':')?;
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return None;
+        }
+        Some((key.to_string(), value.trim().parse().ok()?))
+    }).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub name: String,
+    pub properties: Vec<(String, i64)>,
+}
+
+impl Record {
+    /// `Name: key: value, key: value, ...`
+    pub fn parse(line: &str) -> Option<Record> {
+        let (name, properties) = line.trim().split_once(": ")?;
+        Some(Record { name: name.to_string(), properties: parse_properties(properties)? })
+    }
+
+    /// One record per non-blank line.
+    pub fn parse_all(input: &str) -> Result<Vec<Record>, MatchError> {
+        input.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| Record::parse(line).ok_or_else(|| MatchError::Parse { line: idx + 1, text: line.to_string() }))
+            .collect()
+    }
+
+    /// One `key: value` per non-blank line, as a machine would print them.
+    pub fn parse_readout(input: &str) -> Result<Vec<(String, i64)>, MatchError> {
+        input.lines().enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(idx, line)| {
+                parse_properties(line).filter(|properties| properties.len() == 1).map(|mut properties| properties.remove(0))
+                    .ok_or_else(|| MatchError::Parse { line: idx + 1, text: line.to_string() })
+            })
+            .collect()
+    }
+
+    /// The number ending the name, like 40 for `Sue 40`.
+    pub fn number(&self) -> Option<u32> {
+        self.name.rsplit(' ').next()?.parse().ok()
+    }
+}
+
+/// How a record's value has to relate to the readout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparator {
+    Eq,
+    /// More than the readout says.
+    Gt,
+    /// Less than the readout says.
+    Lt,
+    /// Within these bounds, both included, whatever the readout says.
+    Range(i64, i64),
+}
+
+impl Comparator {
+    pub fn accepts(&self, value: i64, expected: i64) -> bool {
+        match self {
+            Comparator::Eq => value == expected,
+            Comparator::Gt => value > expected,
+            Comparator::Lt => value < expected,
+            Comparator::Range(low, high) => (*low..=*high).contains(&value),
+        }
+    }
+}
+
+impl FromStr for Comparator {
+    type Err = MatchError;
+
+    fn from_str(text: &str) -> Result<Comparator, MatchError> {
+        let error = || MatchError::Comparator(text.to_string());
+        match text {
+            "eq" => Ok(Comparator::Eq),
+            "gt" => Ok(Comparator::Gt),
+            "lt" => Ok(Comparator::Lt),
+            _ => {
+                let (low, high) = text.split_once("..=").ok_or_else(error)?;
+                Ok(Comparator::Range(low.parse().map_err(|_| error())?, high.parse().map_err(|_| error())?))
+            },
+        }
+    }
+}
+
+impl Display for Comparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Comparator::Eq => write!(f, "eq"),
+            Comparator::Gt => write!(f, "gt"),
+            Comparator::Lt => write!(f, "lt"),
+            Comparator::Range(low, high) => write!(f, "{low}..={high}"),
+        }
+    }
+}
+
+/// How well one record fits.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Candidate<'a> {
+    pub record: &'a Record,
+    /// Properties that fit the readout.
+    pub matched: usize,
+    /// Properties that contradict it.
+    pub violated: Vec<&'a str>,
+    /// Properties the readout says nothing about.
+    pub unknown: usize,
+}
+
+impl Candidate<'_> {
+    /// Nothing about the record contradicts the readout.
+    pub fn is_consistent(&self) -> bool {
+        self.violated.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Matcher {
+    readout: HashMap<String, i64>,
+    comparators: HashMap<String, Comparator>,
+}
+
+impl Matcher {
+    /// Every property compared for equality.
+    pub fn new(readout: Vec<(String, i64)>) -> Matcher {
+        Matcher { readout: readout.into_iter().collect(), comparators: HashMap::new() }
+    }
+
+    pub fn compare(mut self, property: &str, comparator: Comparator) -> Matcher {
+        self.comparators.insert(property.to_string(), comparator);
+        self
+    }
+
+    pub fn candidate<'a>(&self, record: &'a Record) -> Candidate<'a> {
+        let mut candidate = Candidate { record, matched: 0, violated: vec![], unknown: 0 };
+        for (key, value) in &record.properties {
+            let comparator = self.comparators.get(key).copied().unwrap_or(Comparator::Eq);
+            match self.readout.get(key) {
+                // a range does not need the readout
+                None if !matches!(comparator, Comparator::Range(..)) => candidate.unknown += 1,
+                expected if comparator.accepts(*value, expected.copied().unwrap_or_default()) => candidate.matched += 1,
+                _ => candidate.violated.push(key),
+            }
+        }
+        candidate
+    }
+
+    /// Every record, most matched properties first, then fewest violated,
+    /// then in input order.
+    pub fn rank<'a>(&self, records: &'a [Record]) -> Vec<Candidate<'a>> {
+        let mut candidates = records.iter().map(|record| self.candidate(record)).collect::<Vec<_>>();
+        candidates.sort_by_key(|candidate| (Reverse(candidate.matched), candidate.violated.len()));
+        candidates
+    }
+
+    /// The consistent record with the most matched properties.
+    pub fn best<'a>(&self, records: &'a [Record]) -> Option<&'a Record> {
+        self.rank(records).into_iter().find(Candidate::is_consistent).map(|candidate| candidate.record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const READOUT: &str = "children: 3\ncats: 7\ntrees: 3\ngoldfish: 5\n";
+
+    #[test]
+    fn test_parse() {
+        let record = Record::parse("Sue 12: cats: 8, trees: -1").unwrap();
+        assert_eq!(record.number(), Some(12));
+        assert_eq!(record.properties, [("cats".to_string(), 8), ("trees".to_string(), -1)]);
+        assert_eq!(Record::parse_all("Sue 1: cats\n"), Err(MatchError::Parse { line: 1, text: "Sue 1: cats".to_string() }));
+        assert!(Record::parse_readout("cats: 1, dogs: 2").is_err());
+        assert_eq!("-2..=4".parse(), Ok(Comparator::Range(-2, 4)));
+        assert_eq!("ge".parse::<Comparator>(), Err(MatchError::Comparator("ge".to_string())));
+    }
+
+    #[test]
+    fn test_rank() {
+        let records = Record::parse_all("Sue 1: cats: 7, dogs: 1\nSue 2: cats: 9, trees: 3, goldfish: 4\nSue 3: children: 3, cats: 8\n").unwrap();
+        let exact = Matcher::new(Record::parse_readout(READOUT).unwrap());
+        let ranked = exact.rank(&records);
+        assert_eq!(ranked.iter().map(|c| (c.record.number().unwrap(), c.matched, c.violated.len(), c.unknown)).collect::<Vec<_>>(),
+            [(1, 1, 0, 1), (3, 1, 1, 0), (2, 1, 2, 0)]);
+        assert_eq!(exact.best(&records).and_then(Record::number), Some(1));
+
+        let ranges = exact.compare("cats", Comparator::Gt).compare("goldfish", Comparator::Lt).compare("dogs", Comparator::Range(2, 3));
+        assert_eq!(ranges.rank(&records)[0].violated, Vec::<&str>::new());
+        assert_eq!(ranges.best(&records).and_then(Record::number), Some(2));
+    }
+}
@@ -0,0 +1,253 @@
+/*
+ * A turn-based fight against a boss with gear from a shop: the shop is read
+ * from its price list, loadouts are every pick that fits the slot rules, and
+ * fights are settled by counting the turns each side needs.
+ * Example: `Shop::parse(text)?.loadouts(&SlotRule::standard())?`.
+ */
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::fmt::Display;
+
+lazy_static! {
+    static ref CATEGORY_REGEX: Regex = Regex::new(r"^(?<category>\w+):\s+Cost\s+Damage\s+Armor$").unwrap();
+    static ref ITEM_REGEX: Regex = Regex::new(r"^(?<name>.+?)\s+(?<cost>\d+)\s+(?<damage>\d+)\s+(?<armor>\d+)$").unwrap();
+    static ref STAT_REGEX: Regex = Regex::new(r"^(?<stat>Hit Points|Damage|Armor): (?<value>\d+)$").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RpgError {
+    Parse { line: usize, text: String },
+    MissingStat(String),
+    UnknownCategory(String),
+    /// A loadout's total cost, damage or armor.
+    Overflow(String),
+}
+
+impl Display for RpgError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpgError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            RpgError::MissingStat(stat) => write!(f, "no `{stat}` given"),
+            RpgError::UnknownCategory(category) => write!(f, "the shop sells no `{category}`"),
+            RpgError::Overflow(stat) => write!(f, "the loadout's total {stat} does not fit in 32 bits"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fighter {
+    pub hp: u32,
+    pub damage: u32,
+    pub armor: u32,
+}
+
+impl Fighter {
+    /// `Hit Points: n`, `Damage: n` and `Armor: n`, one per line.
+    pub fn parse(input: &str) -> Result<Fighter, RpgError> {
+        let (mut hp, mut damage, mut armor) = (None, None, None);
+        for (idx, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let error = || RpgError::Parse { line: idx + 1, text: line.to_string() };
+            let captured = STAT_REGEX.captures(line.trim()).ok_or_else(error)?;
+            let value = captured["value"].parse().map_err(|_| error())?;
+            match &captured["stat"] {
+                "Hit Points" => hp = Some(value),
+                "Damage" => damage = Some(value),
+                _ => armor = Some(value),
+            }
+        }
+        let missing = |stat: &str| RpgError::MissingStat(stat.to_string());
+        Ok(Fighter {
+            hp: hp.ok_or_else(|| missing("Hit Points"))?,
+            damage: damage.ok_or_else(|| missing("Damage"))?,
+            armor: armor.ok_or_else(|| missing("Armor"))?,
+        })
+    }
+
+    /// Turns this fighter needs to bring `other` down; every hit does at
+    /// least 1 damage.
+    pub fn turns_to_kill(&self, other: &Fighter) -> u32 {
+        other.hp.div_ceil(self.damage.saturating_sub(other.armor).max(1))
+    }
+
+    /// Whether this fighter, striking first, wins against `other`.
+    pub fn beats(&self, other: &Fighter) -> bool {
+        self.turns_to_kill(other) <= other.turns_to_kill(self)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Item {
+    pub name: String,
+    pub cost: u32,
+    pub damage: u32,
+    pub armor: u32,
+}
+
+/// Items for sale, by category, in price list order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Shop {
+    pub categories: Vec<(String, Vec<Item>)>,
+}
+
+/// How many different items of a category a loadout holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlotRule {
+    pub category: String,
+    pub min: usize,
+    pub max: usize,
+}
+
+impl SlotRule {
+    pub fn new(category: &str, min: usize, max: usize) -> SlotRule {
+        SlotRule { category: category.to_string(), min, max }
+    }
+
+    /// One weapon, at most one armor and at most two rings.
+    pub fn standard() -> Vec<SlotRule> {
+        vec![SlotRule::new("Weapons", 1, 1), SlotRule::new("Armor", 0, 1), SlotRule::new("Rings", 0, 2)]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loadout {
+    pub items: Vec<Item>,
+}
+
+impl Loadout {
+    fn total(&self, stat: &str, value: fn(&Item) -> u32) -> Result<u32, RpgError> {
+        self.items.iter()
+            .try_fold(0u32, |total, item| total.checked_add(value(item)))
+            .ok_or_else(|| RpgError::Overflow(stat.to_string()))
+    }
+
+    pub fn cost(&self) -> Result<u32, RpgError> {
+        self.total("cost", |item| item.cost)
+    }
+
+    /// The player wearing this loadout.
+    pub fn equip(&self, hp: u32) -> Result<Fighter, RpgError> {
+        Ok(Fighter { hp, damage: self.total("damage", |item| item.damage)?, armor: self.total("armor", |item| item.armor)? })
+    }
+
+    /// `Dagger, Leather`, or `nothing`.
+    pub fn describe(&self) -> String {
+        match self.items.is_empty() {
+            true => "nothing".to_string(),
+            false => self.items.iter().map(|item| item.name.as_str()).join(", "),
+        }
+    }
+}
+
+impl Shop {
+    /// Price lists like `Weapons:    Cost  Damage  Armor` followed by one item
+    /// per line, separated by blank lines.
+    pub fn parse(input: &str) -> Result<Shop, RpgError> {
+        let mut shop = Shop::default();
+        for (idx, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let error = || RpgError::Parse { line: idx + 1, text: line.to_string() };
+            if let Some(captured) = CATEGORY_REGEX.captures(line.trim()) {
+                shop.categories.push((captured["category"].to_string(), vec![]));
+                continue;
+            }
+            let captured = ITEM_REGEX.captures(line.trim()).ok_or_else(error)?;
+            let (_, items) = shop.categories.last_mut().ok_or_else(error)?;
+            items.push(Item {
+                name: captured["name"].to_string(),
+                cost: captured["cost"].parse().map_err(|_| error())?,
+                damage: captured["damage"].parse().map_err(|_| error())?,
+                armor: captured["armor"].parse().map_err(|_| error())?,
+            });
+        }
+        Ok(shop)
+    }
+
+    pub fn items(&self, category: &str) -> Option<&[Item]> {
+        self.categories.iter().find(|(name, _)| name == category).map(|(_, items)| &items[..])
+    }
+
+    /// Every loadout following `rules`, one rule per category bought from.
+    pub fn loadouts(&self, rules: &[SlotRule]) -> Result<Vec<Loadout>, RpgError> {
+        let picks = rules.iter().map(|rule| {
+            let items = self.items(&rule.category).ok_or_else(|| RpgError::UnknownCategory(rule.category.clone()))?;
+            Ok((rule.min..=rule.max.min(items.len()))
+                .flat_map(|count| items.iter().combinations(count))
+                .collect::<Vec<_>>())
+        }).collect::<Result<Vec<_>, RpgError>>()?;
+        Ok(picks.into_iter()
+            .multi_cartesian_product()
+            .map(|pick| Loadout { items: pick.into_iter().flatten().cloned().collect() })
+            .collect())
+    }
+}
+
+/// Each loadout's cost and whether it beats `boss`.
+fn outcomes<'a>(loadouts: &'a [Loadout], hp: u32, boss: &Fighter) -> Result<Vec<(u32, bool, &'a Loadout)>, RpgError> {
+    loadouts.iter().map(|loadout| Ok((loadout.cost()?, loadout.equip(hp)?.beats(boss), loadout))).collect()
+}
+
+/// The cheapest loadout that beats `boss`.
+pub fn cheapest_win(loadouts: &[Loadout], hp: u32, boss: &Fighter) -> Result<Option<Loadout>, RpgError> {
+    Ok(outcomes(loadouts, hp, boss)?.into_iter()
+        .filter(|&(_, won, _)| won)
+        .min_by_key(|&(cost, _, _)| cost)
+        .map(|(_, _, loadout)| loadout.clone()))
+}
+
+/// The most expensive loadout that still loses to `boss`.
+pub fn priciest_loss(loadouts: &[Loadout], hp: u32, boss: &Fighter) -> Result<Option<Loadout>, RpgError> {
+    Ok(outcomes(loadouts, hp, boss)?.into_iter()
+        .filter(|&(_, won, _)| !won)
+        .max_by_key(|&(cost, _, _)| cost)
+        .map(|(_, _, loadout)| loadout.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SHOP: &str = "Weapons:    Cost  Damage  Armor
+Dagger        8     4       0
+Greataxe     74     8       0
+
+Armor:      Cost  Damage  Armor
+Leather      13     0       1
+
+Rings:      Cost  Damage  Armor
+Damage +1    25     1       0
+Defense +1   20     0       1
+Defense +2   40     0       2
+";
+
+    #[test]
+    fn test_fight() {
+        let boss = Fighter::parse("Hit Points: 12\nDamage: 7\nArmor: 2\n").unwrap();
+        let player = Fighter { hp: 8, damage: 5, armor: 5 };
+        assert_eq!((player.turns_to_kill(&boss), boss.turns_to_kill(&player)), (4, 4));
+        assert!(player.beats(&boss));
+        assert!(!Fighter { hp: 6, ..player }.beats(&boss));
+        assert_eq!(Fighter::parse("Hit Points: 1\nArmor: 0"), Err(RpgError::MissingStat("Damage".to_string())));
+    }
+
+    #[test]
+    fn test_shop() {
+        let shop = Shop::parse(SHOP).unwrap();
+        assert_eq!(shop.items("Rings").map(|rings| rings[0].name.as_str()), Some("Damage +1"));
+        // 2 weapons, 2 armor choices, 1 + 3 + 3 ring choices
+        let loadouts = shop.loadouts(&SlotRule::standard()).unwrap();
+        assert_eq!(loadouts.len(), 28);
+        assert_eq!(shop.loadouts(&[SlotRule::new("Shields", 0, 1)]), Err(RpgError::UnknownCategory("Shields".to_string())));
+
+        let boss = Fighter { hp: 20, damage: 6, armor: 2 };
+        let win = cheapest_win(&loadouts, 10, &boss).unwrap().unwrap();
+        assert_eq!((win.cost(), win.describe()), (Ok(127), "Greataxe, Leather, Defense +2".to_string()));
+        let loss = priciest_loss(&loadouts, 10, &boss).unwrap().unwrap();
+        assert_eq!((loss.cost(), loss.describe()), (Ok(119), "Greataxe, Damage +1, Defense +1".to_string()));
+        assert!(Shop::parse("Dagger 8 4 0").is_err());
+
+        let pricey = Shop::parse("Weapons: Cost Damage Armor\nGoldsword 4294967295 1 0\n\nRings: Cost Damage Armor\nGem 1 0 4294967295\n").unwrap();
+        let loadouts = pricey.loadouts(&[SlotRule::new("Weapons", 1, 1), SlotRule::new("Rings", 0, 1)]).unwrap();
+        assert_eq!(loadouts[1].equip(10).map(|player| player.armor), Ok(u32::MAX));
+        assert_eq!(cheapest_win(&loadouts, 10, &boss), Err(RpgError::Overflow("cost".to_string())));
+    }
+}
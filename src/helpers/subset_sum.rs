@@ -0,0 +1,189 @@
+/*
+ * Subsets of a list of numbers that add up to a target: counted by size with
+ * a table instead of being listed, then listed one at a time straight from
+ * that table, smallest first. Also splits a list into equal-sum groups.
+ * Example: `SubsetSums::new(&containers, 150).count()`.
+ */
+use std::fmt::Display;
+
+/// Most counts `SubsetSums` keeps, 128 MiB worth.
+pub const MAX_CELLS: u128 = 1 << 24;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubsetError {
+    /// The table would need this many counts, more than `MAX_CELLS`.
+    TooLarge(u128),
+    /// Some count does not fit in a `u64`.
+    Overflow,
+}
+
+impl Display for SubsetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SubsetError::TooLarge(cells) => write!(f, "a table of {cells} counts is over the limit of {MAX_CELLS}"),
+            SubsetError::Overflow => write!(f, "too many subsets to count in 64 bits"),
+        }
+    }
+}
+
+/// Counts of the subsets of every suffix of `items`, by size and sum.
+#[derive(Debug, Clone)]
+pub struct SubsetSums {
+    items: Vec<u64>,
+    target: usize,
+    /// `table[idx][size][sum]`: subsets of `items[idx..]` with `size` items
+    /// adding up to `sum`.
+    table: Vec<Vec<Vec<u64>>>,
+    count: u64,
+}
+
+impl SubsetSums {
+    /// Fails when the table is over `MAX_CELLS` or a count passes `u64::MAX`.
+    pub fn new(items: &[u64], target: u64) -> Result<SubsetSums, SubsetError> {
+        let n = items.len();
+        let cells = (n as u128 + 1) * (n as u128 + 1) * (target as u128 + 1);
+        if cells > MAX_CELLS {
+            return Err(SubsetError::TooLarge(cells));
+        }
+        let target = target as usize;
+        let mut table = vec![vec![vec![0u64; target + 1]; n + 1]; n + 1];
+        table[n][0][0] = 1;
+        for idx in (0..n).rev() {
+            for size in 0..=n - idx {
+                for sum in 0..=target {
+                    let mut count = table[idx + 1][size][sum];
+                    if size > 0 && sum as u64 >= items[idx] {
+                        let with = table[idx + 1][size - 1][sum - items[idx] as usize];
+                        count = count.checked_add(with).ok_or(SubsetError::Overflow)?;
+                    }
+                    table[idx][size][sum] = count;
+                }
+            }
+        }
+        let count = table[0].iter().try_fold(0u64, |acc, by_sum| acc.checked_add(by_sum[target])).ok_or(SubsetError::Overflow)?;
+        Ok(SubsetSums { items: items.to_vec(), target, table, count })
+    }
+
+    /// Subsets of any size adding up to the target.
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn count_of_size(&self, size: usize) -> u64 {
+        self.table[0].get(size).map_or(0, |by_sum| by_sum[self.target])
+    }
+
+    /// Fewest items adding up to the target.
+    pub fn min_size(&self) -> Option<usize> {
+        (0..=self.items.len()).find(|&size| self.count_of_size(size) > 0)
+    }
+
+    /// The `rank`-th subset of `size` items, as indices; subsets come in
+    /// lexicographic order of their indices.
+    fn unrank(&self, size: usize, mut rank: u64) -> Vec<usize> {
+        let (mut left, mut sum) = (size, self.target);
+        let mut picked = Vec::with_capacity(size);
+        for idx in 0..self.items.len() {
+            if left == 0 {
+                break;
+            }
+            let item = self.items[idx] as usize;
+            let with = if item <= sum { self.table[idx + 1][left - 1][sum - item] } else { 0 };
+            if rank < with {
+                picked.push(idx);
+                (left, sum) = (left - 1, sum - item);
+            } else {
+                rank -= with;
+            }
+        }
+        picked
+    }
+
+    /// Every subset of `size` items adding up to the target, as indices,
+    /// built one at a time without a search.
+    pub fn of_size(&self, size: usize) -> impl Iterator<Item = Vec<usize>> + '_ {
+        (0..self.count_of_size(size)).map(move |rank| self.unrank(size, rank))
+    }
+
+    /// Every subset adding up to the target, smallest first.
+    pub fn by_size(&self) -> impl Iterator<Item = Vec<usize>> + '_ {
+        (0..=self.items.len()).flat_map(|size| self.of_size(size))
+    }
+}
+
+/// Indices of `items` split into `groups` groups of equal sum, if possible.
+pub fn partition(items: &[u64], groups: usize) -> Option<Vec<Vec<usize>>> {
+    let total = items.iter().sum::<u64>();
+    if groups == 0 || total % groups as u64 != 0 {
+        return None;
+    }
+    let share = total / groups as u64;
+    if items.iter().any(|&item| item > share) {
+        return None;
+    }
+    // largest items first, so dead ends show up early
+    let mut order = (0..items.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&idx| std::cmp::Reverse(items[idx]));
+    let mut loads = vec![0; groups];
+    let mut assigned = vec![0; items.len()];
+    if !fill(items, &order, share, &mut loads, &mut assigned) {
+        return None;
+    }
+    let mut split = vec![vec![]; groups];
+    for (idx, &group) in assigned.iter().enumerate() {
+        split[group].push(idx);
+    }
+    Some(split)
+}
+
+/// Whether `items` can be split into `groups` groups of equal sum.
+pub fn can_partition(items: &[u64], groups: usize) -> bool {
+    partition(items, groups).is_some()
+}
+
+/// Places `order[0]`, then the rest, into groups with room for it. Groups
+/// with the same load are interchangeable, so only the first is tried.
+fn fill(items: &[u64], order: &[usize], share: u64, loads: &mut [u64], assigned: &mut [usize]) -> bool {
+    let Some((&idx, rest)) = order.split_first() else { return true };
+    for group in 0..loads.len() {
+        if loads[group] + items[idx] > share || loads[..group].contains(&loads[group]) {
+            continue;
+        }
+        loads[group] += items[idx];
+        assigned[idx] = group;
+        if fill(items, rest, share, loads, assigned) {
+            return true;
+        }
+        loads[group] -= items[idx];
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count() {
+        let sums = SubsetSums::new(&[20, 15, 10, 5, 5], 25).unwrap();
+        assert_eq!(sums.count(), 4);
+        assert_eq!(sums.min_size(), Some(2));
+        assert_eq!(sums.count_of_size(2), 3);
+        assert_eq!(sums.by_size().collect::<Vec<_>>(), [vec![0, 3], vec![0, 4], vec![1, 2], vec![1, 3, 4]]);
+        assert_eq!(SubsetSums::new(&[3, 4], 5).unwrap().min_size(), None);
+        assert_eq!(SubsetSums::new(&[1; 64], 32).unwrap().count(), 1832624140942590534);
+        assert_eq!(SubsetSums::new(&[1; 68], 34).unwrap_err(), SubsetError::Overflow);
+        assert_eq!(SubsetSums::new(&[1, 2], 1 << 40).unwrap_err(), SubsetError::TooLarge(9 << 40 | 9));
+    }
+
+    #[test]
+    fn test_partition() {
+        let items = [1, 2, 3, 4, 5, 7, 8, 9, 10, 11];
+        let split = partition(&items, 4).unwrap();
+        assert!(split.iter().all(|group| group.iter().map(|&idx| items[idx]).sum::<u64>() == 15));
+        assert!(can_partition(&items, 3));
+        assert!(!can_partition(&items, 7));
+        assert!(!can_partition(&[5, 5, 2], 2));
+        assert!(!can_partition(&[3, 3, 3, 1], 2));
+    }
+}
@@ -0,0 +1,348 @@
+/*
+ * Travelling salesman over a small integer-indexed distance matrix, solved
+ * exactly with Held–Karp dynamic programming.
+ * Example: `tsp::solve(&matrix, Mode::Path, Objective::Min)?.cost`.
+ */
+use std::fmt::Display;
+
+pub type Cost = i64;
+
+/// Largest node count `solve` accepts; the table grows as `2^n * n`.
+pub const MAX_NODES: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Visit every node once, starting and ending anywhere.
+    Path,
+    /// Visit every node once and come back to the first.
+    Cycle,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Objective {
+    Min,
+    Max,
+}
+
+impl Objective {
+    fn better(self, candidate: Cost, current: Cost) -> bool {
+        match self {
+            Objective::Min => candidate < current,
+            Objective::Max => candidate > current,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TspError {
+    Empty,
+    TooLarge(usize),
+    /// Missing edges leave no route through every node.
+    NoRoute,
+}
+
+impl Display for TspError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TspError::Empty => write!(f, "there are no nodes to visit"),
+            TspError::TooLarge(n) => write!(f, "{n} nodes is more than the {MAX_NODES} the solver handles"),
+            TspError::NoRoute => write!(f, "no route visits every node"),
+        }
+    }
+}
+
+/// Directed edge costs between nodes `0..len`; `None` where there is no edge.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceMatrix {
+    len: usize,
+    costs: Vec<Option<Cost>>,
+}
+
+impl DistanceMatrix {
+    pub fn new(len: usize) -> DistanceMatrix {
+        DistanceMatrix { len, costs: vec![None; len * len] }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, from: usize, to: usize) -> Option<Cost> {
+        self.costs[from * self.len + to]
+    }
+
+    pub fn set(&mut self, from: usize, to: usize, cost: Cost) {
+        self.costs[from * self.len + to] = Some(cost);
+    }
+
+    /// Sets both directions.
+    pub fn set_both(&mut self, a: usize, b: usize, cost: Cost) {
+        self.set(a, b, cost);
+        self.set(b, a, cost);
+    }
+
+    /// Each pair's two directions added up, for costs both ends of an edge pay.
+    pub fn pair_sums(&self) -> DistanceMatrix {
+        let mut sums = DistanceMatrix::new(self.len);
+        for a in 0..self.len {
+            for b in 0..self.len {
+                if let (Some(there), Some(back)) = (self.get(a, b), self.get(b, a)) {
+                    sums.set(a, b, there + back);
+                }
+            }
+        }
+        sums
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tour {
+    /// Nodes in visiting order. A cycle lists each node once and closes
+    /// from the last back to the first.
+    pub route: Vec<usize>,
+    pub cost: Cost,
+    pub mode: Mode,
+}
+
+impl Tour {
+    /// Every edge taken as `(from, to, cost)`, the closing one included.
+    pub fn legs(&self, matrix: &DistanceMatrix) -> Vec<(usize, usize, Cost)> {
+        let mut stops = self.route.clone();
+        if self.mode == Mode::Cycle && self.route.len() > 1 {
+            stops.push(self.route[0]);
+        }
+        stops.windows(2).map(|pair| (pair[0], pair[1], matrix.get(pair[0], pair[1]).unwrap())).collect()
+    }
+
+    /// The route on one line, then one indented line per leg.
+    pub fn describe(&self, matrix: &DistanceMatrix, names: &[impl AsRef<str>]) -> String {
+        let name = |node: usize| names[node].as_ref();
+        let mut stops = self.route.iter().map(|&node| name(node)).collect::<Vec<_>>();
+        if self.mode == Mode::Cycle && self.route.len() > 1 {
+            stops.push(name(self.route[0]));
+        }
+        let mut out = format!("{}: {}\n", self.cost, stops.join(" -> "));
+        for (from, to, cost) in self.legs(matrix) {
+            out += &format!("    {} -> {}: {cost:+}\n", name(from), name(to));
+        }
+        out
+    }
+
+    fn reversed(&self) -> Vec<usize> {
+        let mut route = self.route.clone();
+        match self.mode {
+            Mode::Path => route.reverse(),
+            Mode::Cycle => route[1..].reverse(),
+        }
+        route
+    }
+}
+
+/// The best route through every node of `matrix`.
+pub fn solve(matrix: &DistanceMatrix, mode: Mode, objective: Objective) -> Result<Tour, TspError> {
+    let n = matrix.len();
+    if n == 0 {
+        return Err(TspError::Empty);
+    }
+    if n > MAX_NODES {
+        return Err(TspError::TooLarge(n));
+    }
+
+    // best[mask * n + last]: best cost of a route over `mask` ending at `last`
+    let full = (1usize << n) - 1;
+    let mut best: Vec<Option<Cost>> = vec![None; (full + 1) * n];
+    let mut parent = vec![usize::MAX; (full + 1) * n];
+    match mode {
+        Mode::Path => (0..n).for_each(|start| best[(1 << start) * n + start] = Some(0)),
+        Mode::Cycle => best[n] = Some(0),
+    }
+    for mask in 1..=full {
+        for last in (0..n).filter(|last| mask & (1 << last) != 0) {
+            let Some(cost) = best[mask * n + last] else { continue };
+            for next in (0..n).filter(|next| mask & (1 << next) == 0) {
+                let Some(edge) = matrix.get(last, next) else { continue };
+                let slot = (mask | 1 << next) * n + next;
+                if best[slot].is_none_or(|current| objective.better(cost + edge, current)) {
+                    best[slot] = Some(cost + edge);
+                    parent[slot] = last;
+                }
+            }
+        }
+    }
+
+    let closing = |last: usize| match mode {
+        Mode::Path => Some(0),
+        Mode::Cycle if n == 1 => Some(0),
+        Mode::Cycle => matrix.get(last, 0),
+    };
+    let (cost, mut last) = (0..n)
+        .filter_map(|last| Some((best[full * n + last]? + closing(last)?, last)))
+        .reduce(|a, b| if objective.better(b.0, a.0) { b } else { a })
+        .ok_or(TspError::NoRoute)?;
+
+    let mut route = vec![];
+    let mut mask = full;
+    while last != usize::MAX {
+        route.push(last);
+        let previous = parent[mask * n + last];
+        mask &= !(1 << last);
+        last = previous;
+    }
+    route.reverse();
+    Ok(Tour { route, cost, mode })
+}
+
+/// Entry of a k-best table: a route's cost and where its best prefix came from.
+#[derive(Clone, Copy)]
+struct Ranked {
+    cost: Cost,
+    last: usize,
+    rank: usize,
+}
+
+fn insert_ranked(list: &mut Vec<Ranked>, entry: Ranked, k: usize, objective: Objective) {
+    let at = list.partition_point(|other| !objective.better(entry.cost, other.cost));
+    if at < k {
+        list.insert(at, entry);
+        list.truncate(k);
+    }
+}
+
+/// The `k` best routes, best first. When the matrix is symmetric a route and
+/// its reverse are the same route and only one of them is listed.
+pub fn ranked(matrix: &DistanceMatrix, mode: Mode, objective: Objective, k: usize) -> Result<Vec<Tour>, TspError> {
+    let n = matrix.len();
+    if n == 0 {
+        return Err(TspError::Empty);
+    }
+    if n > MAX_NODES {
+        return Err(TspError::TooLarge(n));
+    }
+    let symmetric = (0..n).all(|a| (0..n).all(|b| matrix.get(a, b) == matrix.get(b, a)));
+    // each route shows up twice when reversals are not told apart
+    let keep = if symmetric { 2 * k } else { k };
+
+    let full = (1usize << n) - 1;
+    let mut table: Vec<Vec<Ranked>> = vec![vec![]; (full + 1) * n];
+    let start = Ranked { cost: 0, last: usize::MAX, rank: 0 };
+    match mode {
+        Mode::Path => (0..n).for_each(|node| table[(1 << node) * n + node].push(start)),
+        Mode::Cycle => table[n].push(start),
+    }
+    for mask in 1..=full {
+        for last in (0..n).filter(|last| mask & (1 << last) != 0) {
+            let entries = std::mem::take(&mut table[mask * n + last]);
+            for next in (0..n).filter(|next| mask & (1 << next) == 0) {
+                let Some(edge) = matrix.get(last, next) else { continue };
+                for (rank, entry) in entries.iter().enumerate() {
+                    let candidate = Ranked { cost: entry.cost + edge, last, rank };
+                    insert_ranked(&mut table[(mask | 1 << next) * n + next], candidate, keep, objective);
+                }
+            }
+            table[mask * n + last] = entries;
+        }
+    }
+
+    let mut finals = vec![];
+    for last in 0..n {
+        let closing = match mode {
+            Mode::Path => Some(0),
+            Mode::Cycle if n == 1 => Some(0),
+            Mode::Cycle => matrix.get(last, 0),
+        };
+        let Some(closing) = closing else { continue };
+        for (rank, entry) in table[full * n + last].iter().enumerate() {
+            insert_ranked(&mut finals, Ranked { cost: entry.cost + closing, last, rank }, keep, objective);
+        }
+    }
+    if finals.is_empty() {
+        return Err(TspError::NoRoute);
+    }
+
+    let mut tours: Vec<Tour> = vec![];
+    for end in finals {
+        let mut route = vec![];
+        let (mut mask, mut last, mut rank) = (full, end.last, end.rank);
+        while last != usize::MAX {
+            route.push(last);
+            let entry = table[mask * n + last][rank];
+            mask &= !(1 << last);
+            (last, rank) = (entry.last, entry.rank);
+        }
+        route.reverse();
+        let tour = Tour { route, cost: end.cost, mode };
+        if symmetric && tours.iter().any(|other| other.route == tour.reversed()) {
+            continue;
+        }
+        tours.push(tour);
+    }
+    tours.truncate(k);
+    Ok(tours)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path() {
+        // London, Dublin, Belfast
+        let mut matrix = DistanceMatrix::new(3);
+        matrix.set_both(0, 1, 464);
+        matrix.set_both(0, 2, 518);
+        matrix.set_both(1, 2, 141);
+        let shortest = solve(&matrix, Mode::Path, Objective::Min).unwrap();
+        assert_eq!(shortest.cost, 605);
+        assert!(shortest.route == [0, 1, 2] || shortest.route == [2, 1, 0]);
+        assert_eq!(solve(&matrix, Mode::Path, Objective::Max).unwrap().cost, 982);
+        assert_eq!(solve(&matrix, Mode::Cycle, Objective::Min).unwrap().cost, 1123);
+    }
+
+    #[test]
+    fn test_cycle_and_errors() {
+        let mut matrix = DistanceMatrix::new(4);
+        matrix.set(0, 1, 1);
+        matrix.set(1, 2, 1);
+        matrix.set(2, 3, 1);
+        matrix.set(3, 0, 1);
+        matrix.set(0, 2, 10);
+        assert_eq!(solve(&matrix, Mode::Cycle, Objective::Max), Ok(Tour { route: vec![0, 1, 2, 3], cost: 4, mode: Mode::Cycle }));
+        matrix.set(3, 0, -5);
+        assert_eq!(solve(&matrix, Mode::Cycle, Objective::Min).unwrap().cost, -2);
+        let mut pair = DistanceMatrix::new(2);
+        pair.set(0, 1, 3);
+        pair.set(1, 0, -1);
+        assert_eq!((pair.pair_sums().get(1, 0), matrix.pair_sums().get(3, 0)), (Some(2), None));
+
+        assert_eq!(solve(&DistanceMatrix::new(0), Mode::Path, Objective::Min), Err(TspError::Empty));
+        assert_eq!(solve(&DistanceMatrix::new(3), Mode::Path, Objective::Min), Err(TspError::NoRoute));
+        assert_eq!(solve(&DistanceMatrix::new(1), Mode::Cycle, Objective::Min).unwrap().route, [0]);
+        assert_eq!(solve(&DistanceMatrix::new(17), Mode::Path, Objective::Min), Err(TspError::TooLarge(17)));
+    }
+
+    #[test]
+    fn test_ranked() {
+        let mut matrix = DistanceMatrix::new(4);
+        for (a, b, cost) in [(0, 1, 1), (0, 2, 5), (0, 3, 3), (1, 2, 2), (1, 3, 7), (2, 3, 4)] {
+            matrix.set_both(a, b, cost);
+        }
+        // 4! / 2 distinct paths, every one ranked
+        let tours = ranked(&matrix, Mode::Path, Objective::Min, 20).unwrap();
+        assert_eq!(tours.len(), 12);
+        assert_eq!(tours[0], solve(&matrix, Mode::Path, Objective::Min).unwrap());
+        assert!(tours.windows(2).all(|pair| pair[0].cost <= pair[1].cost));
+        assert_eq!(tours.iter().map(|tour| tour.cost).take(3).collect::<Vec<_>>(), [6, 7, 8]);
+
+        let tours = ranked(&matrix, Mode::Cycle, Objective::Max, 5).unwrap();
+        assert_eq!(tours.iter().map(|tour| tour.cost).collect::<Vec<_>>(), [17, 17, 10]);
+        let tour = Tour { route: vec![0, 1, 2, 3], cost: 10, mode: Mode::Cycle };
+        assert!(tours[2] == tour || tours[2].route == tour.reversed());
+        assert_eq!(tour.legs(&matrix), [(0, 1, 1), (1, 2, 2), (2, 3, 4), (3, 0, 3)]);
+        let names = ["A", "B", "C", "D"];
+        assert!(tour.describe(&matrix, &names).starts_with("10: A -> B -> C -> D -> A\n    A -> B: +1\n"));
+    }
+}
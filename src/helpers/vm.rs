@@ -0,0 +1,513 @@
+/*
+ * Register machine for day 23, with room for the wider assembunny family.
+ * Example: `let mut machine = Machine::new(parse_program(input)?); machine.run()?;`
+ */
+use std::fmt::Display;
+
+pub mod analysis;
+pub mod asm;
+pub mod isa;
+pub mod optimize;
+
+pub use isa::{Assembunny, Day23, Extended, InstructionSet};
+
+pub type Value = i64;
+
+/// Index into a program's register table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Register(pub usize);
+
+impl Register {
+    pub const A: Register = Register(0);
+    pub const B: Register = Register(1);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operand {
+    Reg(Register),
+    Imm(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Hlf(Register),
+    Tpl(Register),
+    Inc(Register),
+    Dec(Register),
+    Jmp(i64),
+    Jie(Register, i64),
+    Jio(Register, i64),
+    Cpy(Operand, Register),
+    /// Jumps by the second operand when the first is not zero.
+    Jnz(Operand, Operand),
+    Out(Operand),
+}
+
+impl Instruction {
+    /// The relative jump of the instruction when it is a constant.
+    pub fn jump_offset(&self) -> Option<i64> {
+        match *self {
+            Instruction::Jmp(offset) | Instruction::Jie(_, offset) | Instruction::Jio(_, offset) => Some(offset),
+            Instruction::Jnz(_, Operand::Imm(offset)) => Some(offset),
+            _ => None,
+        }
+    }
+
+    pub fn mnemonic(&self) -> &'static str {
+        match self {
+            Instruction::Hlf(_) => "hlf",
+            Instruction::Tpl(_) => "tpl",
+            Instruction::Inc(_) => "inc",
+            Instruction::Dec(_) => "dec",
+            Instruction::Jmp(_) => "jmp",
+            Instruction::Jie(..) => "jie",
+            Instruction::Jio(..) => "jio",
+            Instruction::Cpy(..) => "cpy",
+            Instruction::Jnz(..) => "jnz",
+            Instruction::Out(_) => "out",
+        }
+    }
+}
+
+/// Instructions plus the names of the registers they use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub instructions: Vec<Instruction>,
+    pub registers: Vec<String>,
+}
+
+impl Program {
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn register(&self, name: &str) -> Result<Register, VmError> {
+        self.registers.iter()
+            .position(|register| register == name)
+            .map(Register)
+            .ok_or_else(|| VmError::UnknownRegister(name.to_string()))
+    }
+
+    pub fn register_name(&self, r: Register) -> &str {
+        &self.registers[r.0]
+    }
+
+    fn operand_text(&self, operand: Operand) -> String {
+        match operand {
+            Operand::Reg(r) => self.registers[r.0].clone(),
+            Operand::Imm(value) => value.to_string(),
+        }
+    }
+
+    /// `instruction` in source syntax, using this program's register names.
+    pub fn format(&self, instruction: &Instruction) -> String {
+        let name = |r: Register| self.registers[r.0].as_str();
+        let mnemonic = instruction.mnemonic();
+        match *instruction {
+            Instruction::Hlf(r) | Instruction::Tpl(r) | Instruction::Inc(r) | Instruction::Dec(r) => {
+                format!("{mnemonic} {}", name(r))
+            },
+            Instruction::Jmp(offset) => format!("{mnemonic} {offset:+}"),
+            Instruction::Jie(r, offset) | Instruction::Jio(r, offset) => format!("{mnemonic} {}, {offset:+}", name(r)),
+            Instruction::Cpy(src, r) => format!("{mnemonic} {} {}", self.operand_text(src), name(r)),
+            Instruction::Jnz(test, Operand::Imm(offset)) => format!("{mnemonic} {} {offset:+}", self.operand_text(test)),
+            Instruction::Jnz(test, offset) => format!("{mnemonic} {} {}", self.operand_text(test), self.operand_text(offset)),
+            Instruction::Out(src) => format!("{mnemonic} {}", self.operand_text(src)),
+        }
+    }
+
+    /// `12` breaks on an address, `b==1`, `b!=1`, `a<10` or `a>10` on a register.
+    pub fn breakpoint(&self, text: &str) -> Result<Breakpoint, VmError> {
+        if let Ok(pc) = text.parse::<usize>() {
+            return Ok(Breakpoint::Pc(pc));
+        }
+        let parse_error = || VmError::Parse(text.to_string());
+        let (reg, cmp, value) = ["==", "!=", "<", ">"].iter()
+            .find_map(|cmp| text.split_once(cmp).map(|(reg, value)| (reg, *cmp, value)))
+            .ok_or_else(parse_error)?;
+        let value = value.trim().parse::<Value>().map_err(|_| parse_error())?;
+        let condition = match cmp {
+            "==" => Condition::Eq(value),
+            "!=" => Condition::Ne(value),
+            "<" => Condition::Lt(value),
+            _ => Condition::Gt(value),
+        };
+        Ok(Breakpoint::Register(self.register(reg.trim())?, condition))
+    }
+}
+
+/// Reads a 2015 day 23 program.
+pub fn parse_program(input: &str) -> Result<Program, VmError> {
+    parse_program_with(input, &Day23)
+}
+
+/// Reads a program one instruction per line, decoded by `isa`. Registers are
+/// numbered in the order `isa` declares them, then in order of first use.
+pub fn parse_program_with(input: &str, isa: &dyn InstructionSet) -> Result<Program, VmError> {
+    let mut registers = isa.registers().iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    let instructions = input.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| isa::decode_line(isa, line, &mut registers))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(Program { instructions, registers })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VmError {
+    Parse(String),
+    UnknownOpcode(String),
+    UnknownRegister(String),
+    UnknownLabel(String),
+    DuplicateLabel(String),
+    Overflow { pc: usize, instruction: Instruction },
+}
+
+impl Display for VmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VmError::Parse(line) => write!(f, "cannot parse instruction `{line}`"),
+            VmError::UnknownOpcode(inst) => write!(f, "unknown instruction `{inst}`"),
+            VmError::UnknownRegister(reg) => write!(f, "unknown register `{reg}`"),
+            VmError::UnknownLabel(label) => write!(f, "unknown label `{label}`"),
+            VmError::DuplicateLabel(label) => write!(f, "label `{label}` is defined more than once"),
+            VmError::Overflow { pc, instruction } => {
+                write!(f, "register overflow at {pc}: `{}`", instruction.mnemonic())
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Condition {
+    Eq(Value),
+    Ne(Value),
+    Lt(Value),
+    Gt(Value),
+}
+
+impl Condition {
+    pub fn holds(&self, value: Value) -> bool {
+        match *self {
+            Condition::Eq(v) => value == v,
+            Condition::Ne(v) => value != v,
+            Condition::Lt(v) => value < v,
+            Condition::Gt(v) => value > v,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// Stops before the instruction at this address runs.
+    Pc(usize),
+    /// Stops before the next instruction once the register matches.
+    Register(Register, Condition),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// The program counter left the program.
+    Halted,
+    /// Index into the machine's breakpoints.
+    Breakpoint(usize),
+    LimitReached,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub step: u64,
+    pub pc: usize,
+    pub instruction: Instruction,
+    /// Registers after the instruction ran.
+    pub registers: Vec<Value>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine {
+    program: Program,
+    pub registers: Vec<Value>,
+    pc: i64,
+    steps: u64,
+    limit: Option<u64>,
+    breakpoints: Vec<Breakpoint>,
+    /// Whether the last `run` stopped on a breakpoint with no step since.
+    at_breakpoint: bool,
+    trace: Option<Vec<TraceEntry>>,
+    output: Vec<Value>,
+    kernels: Vec<Option<optimize::Kernel>>,
+}
+
+impl Machine {
+    pub fn new(program: Program) -> Machine {
+        Machine {
+            registers: vec![0; program.registers.len()],
+            program,
+            pc: 0,
+            steps: 0,
+            limit: None,
+            breakpoints: vec![],
+            at_breakpoint: false,
+            trace: None,
+            output: vec![],
+            kernels: vec![],
+        }
+    }
+
+    pub fn program(&self) -> &Program {
+        &self.program
+    }
+
+    pub fn pc(&self) -> i64 {
+        self.pc
+    }
+
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn register(&self, r: Register) -> Value {
+        self.registers[r.0]
+    }
+
+    pub fn set_register(&mut self, r: Register, value: Value) {
+        self.registers[r.0] = value;
+    }
+
+    pub fn register_named(&self, name: &str) -> Result<Value, VmError> {
+        Ok(self.register(self.program.register(name)?))
+    }
+
+    /// Values written by `out`, oldest first.
+    pub fn output(&self) -> &[Value] {
+        &self.output
+    }
+
+    /// Caps the total number of executed instructions, `None` for no cap.
+    pub fn set_limit(&mut self, limit: Option<u64>) {
+        self.limit = limit;
+    }
+
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(breakpoint);
+        self.breakpoints.len() - 1
+    }
+
+    pub fn clear_breakpoints(&mut self) {
+        self.breakpoints.clear();
+    }
+
+    /// Starts recording every executed instruction.
+    pub fn enable_trace(&mut self) {
+        self.trace.get_or_insert_with(Vec::new);
+    }
+
+    /// Hands back what was recorded so far, leaving the trace empty.
+    pub fn take_trace(&mut self) -> Vec<TraceEntry> {
+        self.trace.as_mut().map(std::mem::take).unwrap_or_default()
+    }
+
+    /// One trace line: step, address, instruction and every register.
+    pub fn format_trace(&self, entry: &TraceEntry) -> String {
+        let registers = self.program.registers.iter().zip(&entry.registers)
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>();
+        format!("{:>8} {:>4}: {:<12} {}", entry.step, entry.pc, self.program.format(&entry.instruction), registers.join(" "))
+    }
+
+    /// Shortcuts from `optimize::optimize`, indexed by address. They are skipped
+    /// while tracing or with breakpoints set, as they jump over the states in between.
+    pub fn set_kernels(&mut self, kernels: Vec<Option<optimize::Kernel>>) {
+        self.kernels = kernels;
+    }
+
+    /// Runs the kernel at the current address if there is one and it fits in the limit.
+    fn run_kernel(&mut self, pc: usize) -> bool {
+        if self.trace.is_some() || !self.breakpoints.is_empty() {
+            return false;
+        }
+        let Some(effect) = self.kernels.get(pc).copied().flatten().and_then(|kernel| kernel.apply(&self.registers)) else {
+            return false;
+        };
+        if self.limit.is_some_and(|limit| self.steps + effect.steps > limit) {
+            return false;
+        }
+        for (r, value) in effect.writes {
+            self.registers[r.0] = value;
+        }
+        self.pc = self.pc.saturating_add(effect.jump);
+        self.steps += effect.steps;
+        true
+    }
+
+    pub fn is_halted(&self) -> bool {
+        !(0..self.program.len() as i64).contains(&self.pc)
+    }
+
+    fn value(&self, operand: Operand) -> Value {
+        match operand {
+            Operand::Reg(r) => self.registers[r.0],
+            Operand::Imm(value) => value,
+        }
+    }
+
+    /// Executes a single instruction (or a whole kernel), `Ok(false)` once the program has halted.
+    pub fn step(&mut self) -> Result<bool, VmError> {
+        if self.is_halted() {
+            return Ok(false);
+        }
+        self.at_breakpoint = false;
+        let pc = self.pc as usize;
+        if self.run_kernel(pc) {
+            return Ok(true);
+        }
+        let instruction = self.program.instructions[pc];
+        let overflow = || VmError::Overflow { pc, instruction };
+        let mut jump = 1;
+        match instruction {
+            Instruction::Hlf(r) => self.registers[r.0] /= 2,
+            Instruction::Tpl(r) => {
+                self.registers[r.0] = self.registers[r.0].checked_mul(3).ok_or_else(overflow)?
+            },
+            Instruction::Inc(r) => {
+                self.registers[r.0] = self.registers[r.0].checked_add(1).ok_or_else(overflow)?
+            },
+            Instruction::Dec(r) => {
+                self.registers[r.0] = self.registers[r.0].checked_sub(1).ok_or_else(overflow)?
+            },
+            Instruction::Jmp(offset) => jump = offset,
+            Instruction::Jie(r, offset) => if self.registers[r.0] % 2 == 0 { jump = offset },
+            Instruction::Jio(r, offset) => if self.registers[r.0] == 1 { jump = offset },
+            Instruction::Cpy(src, r) => self.registers[r.0] = self.value(src),
+            Instruction::Jnz(test, offset) => if self.value(test) != 0 { jump = self.value(offset) },
+            Instruction::Out(src) => self.output.push(self.value(src)),
+        }
+        // a jump past the last address halts like any other jump out of the program
+        self.pc = self.pc.saturating_add(jump);
+        self.steps += 1;
+        if let Some(trace) = self.trace.as_mut() {
+            trace.push(TraceEntry { step: self.steps, pc, instruction, registers: self.registers.clone() });
+        }
+        Ok(true)
+    }
+
+    fn hit_breakpoint(&self) -> Option<usize> {
+        self.breakpoints.iter().position(|breakpoint| match *breakpoint {
+            Breakpoint::Pc(pc) => self.pc == pc as i64,
+            Breakpoint::Register(r, condition) => condition.holds(self.registers[r.0]),
+        })
+    }
+
+    /// Runs until the program halts, a breakpoint matches or the limit is hit.
+    /// Breakpoints are checked before the first instruction too; resuming
+    /// after a breakpoint always executes at least one instruction.
+    pub fn run(&mut self) -> Result<Stop, VmError> {
+        loop {
+            if self.is_halted() {
+                return Ok(Stop::Halted);
+            }
+            if !self.at_breakpoint {
+                if let Some(idx) = self.hit_breakpoint() {
+                    self.at_breakpoint = true;
+                    return Ok(Stop::Breakpoint(idx));
+                }
+            }
+            if self.limit.is_some_and(|limit| self.steps >= limit) {
+                return Ok(Stop::LimitReached);
+            }
+            self.step()?;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLATZ: &str = "jio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7";
+
+    #[test]
+    fn test_parse() {
+        let program = parse_program(&crate::read_file("examples", 23)).unwrap();
+        assert_eq!(program.instructions, vec![
+            Instruction::Inc(Register::A),
+            Instruction::Jio(Register::A, 2),
+            Instruction::Tpl(Register::A),
+            Instruction::Inc(Register::A),
+        ]);
+        assert_eq!(program.format(&program.instructions[1]), "jio a, +2");
+        assert_eq!(parse_program("jmp -7").unwrap().instructions, vec![Instruction::Jmp(-7)]);
+        assert_eq!(parse_program("inc c"), Err(VmError::UnknownRegister("c".to_string())));
+        assert_eq!(parse_program("mul a"), Err(VmError::UnknownOpcode("mul".to_string())));
+        assert_eq!(parse_program("jmp a"), Err(VmError::Parse("jmp a".to_string())));
+    }
+
+    #[test]
+    fn test_run() {
+        let mut machine = Machine::new(parse_program(&crate::read_file("examples", 23)).unwrap());
+        assert_eq!(machine.run(), Ok(Stop::Halted));
+        assert_eq!(machine.registers, [2, 0]);
+        assert_eq!(machine.steps(), 3);
+
+        let mut machine = Machine::new(parse_program(COLLATZ).unwrap());
+        machine.set_register(Register::A, 27);
+        assert_eq!(machine.run(), Ok(Stop::Halted));
+        assert_eq!(machine.register(Register::B), 111);
+
+        let mut machine = Machine::new(parse_program("inc a\njmp +9223372036854775807").unwrap());
+        assert_eq!(machine.run(), Ok(Stop::Halted));
+        assert_eq!(machine.steps(), 2);
+    }
+
+    #[test]
+    fn test_breakpoints_and_limit() {
+        let program = parse_program(COLLATZ).unwrap();
+        let done = program.breakpoint("a==1").unwrap();
+        let mut machine = Machine::new(program);
+        machine.set_register(Register::A, 6);
+        machine.enable_trace();
+        // a breakpoint already matching stops a fresh machine before it starts
+        let start = machine.add_breakpoint(Breakpoint::Pc(0));
+        assert_eq!(machine.run(), Ok(Stop::Breakpoint(start)));
+        assert_eq!(machine.steps(), 0);
+        machine.clear_breakpoints();
+        let odd = machine.add_breakpoint(Breakpoint::Pc(3));
+        assert_eq!(machine.run(), Ok(Stop::Breakpoint(odd)));
+        assert_eq!(machine.registers, [3, 2]);
+        let trace = machine.take_trace();
+        assert_eq!(machine.format_trace(trace.last().unwrap()).split_whitespace().collect::<Vec<_>>(),
+            ["8", "2:", "jie", "a,", "+4", "a=3", "b=2"]);
+
+        machine.clear_breakpoints();
+        let done = machine.add_breakpoint(done);
+        assert_eq!(machine.run(), Ok(Stop::Breakpoint(done)));
+        assert_eq!(machine.registers, [1, 8]);
+
+        machine.clear_breakpoints();
+        machine.set_limit(Some(machine.steps() + 1));
+        assert_eq!(machine.run(), Ok(Stop::LimitReached));
+        assert_eq!(machine.pc(), 0);
+        assert_eq!(machine.take_trace().len(), machine.steps() as usize - 8);
+    }
+
+    #[test]
+    fn test_assembunny() {
+        let program = parse_program_with("cpy 41 a\ninc a\ninc a\ndec a\njnz a 2\ndec a", &Assembunny).unwrap();
+        assert_eq!(program.registers, ["a", "b", "c", "d"]);
+        let mut machine = Machine::new(program);
+        assert_eq!(machine.run(), Ok(Stop::Halted));
+        assert_eq!(machine.register_named("a"), Ok(42));
+
+        // counts down `n` into the output, in a register only this program uses
+        let source = "cpy 3 n\nout n\ndec n\njnz n -2\nhlf a";
+        assert_eq!(parse_program_with(source, &Assembunny), Err(VmError::UnknownOpcode("hlf".to_string())));
+        let program = parse_program_with(source, &Extended).unwrap();
+        assert_eq!(program.registers, ["a", "b", "c", "d", "n"]);
+        let mut machine = Machine::new(program);
+        machine.run().unwrap();
+        assert_eq!(machine.output(), [3, 2, 1]);
+    }
+}
@@ -0,0 +1,242 @@
+/*
+ * Static analysis for day 23 programs: basic blocks, control-flow graph,
+ * loops, unreachable code and jumps leaving the program.
+ * Example: `println!("{}", analysis::analyze(&program.instructions));`
+ */
+use super::{Instruction, Operand};
+use petgraph::algo::dominators;
+use petgraph::graph::{DiGraph, NodeIndex};
+use petgraph::visit::{Dfs, EdgeRef};
+use petgraph::Direction;
+use std::collections::BTreeSet;
+use std::fmt::Display;
+use std::ops::Range;
+
+/// Instructions `start..end`, entered only at `start` and left only after `end - 1`.
+/// The last block of the graph is the empty exit block at `program.len()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Loop {
+    /// Block every iteration goes through.
+    pub header: usize,
+    /// Blocks of the loop body, header included, in address order.
+    pub blocks: Vec<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub blocks: Vec<BasicBlock>,
+    /// One node per block (`NodeIndex::new(idx)` is `blocks[idx]`).
+    pub cfg: DiGraph<BasicBlock, ()>,
+    pub loops: Vec<Loop>,
+    pub unreachable: Vec<Range<usize>>,
+    /// `(pc, target)` of every jump landing outside the program, other than its end.
+    pub out_of_range: Vec<(usize, i64)>,
+    /// Jumps whose offset is read from a register, so their target is unknown.
+    pub indirect: Vec<usize>,
+}
+
+impl Analysis {
+    pub fn exit(&self) -> usize {
+        self.blocks.len() - 1
+    }
+
+    pub fn block_of(&self, pc: usize) -> Option<usize> {
+        self.blocks.iter().position(|block| (block.start..block.end).contains(&pc))
+    }
+
+    pub fn has_issues(&self) -> bool {
+        !self.unreachable.is_empty() || !self.out_of_range.is_empty() || !self.indirect.is_empty()
+    }
+
+    /// One line per unreachable range and per jump leaving the program.
+    pub fn warnings(&self) -> Vec<String> {
+        let unreachable = self.unreachable.iter()
+            .map(|range| format!("warning: instructions {}..{} are unreachable", range.start, range.end));
+        let out_of_range = self.out_of_range.iter()
+            .map(|(pc, target)| format!("warning: jump at {pc} leaves the program (target {target})"));
+        let indirect = self.indirect.iter()
+            .map(|pc| format!("warning: jump at {pc} has a computed target, the graph may miss edges"));
+        unreachable.chain(out_of_range).chain(indirect).collect()
+    }
+}
+
+/// Every address control may go to after `pc`. Jumps by a register only
+/// list the fall-through.
+pub fn successors(instruction: &Instruction, pc: usize) -> Vec<i64> {
+    let next = pc as i64 + 1;
+    match *instruction {
+        Instruction::Jmp(offset) => vec![pc as i64 + offset],
+        Instruction::Jnz(Operand::Imm(test), Operand::Imm(offset)) => {
+            vec![if test != 0 { pc as i64 + offset } else { next }]
+        },
+        Instruction::Jie(_, offset) | Instruction::Jio(_, offset) | Instruction::Jnz(_, Operand::Imm(offset)) => {
+            vec![next, pc as i64 + offset]
+        },
+        Instruction::Jnz(_, Operand::Reg(_)) => vec![next],
+        _ => vec![next],
+    }
+}
+
+fn ends_block(instruction: &Instruction) -> bool {
+    matches!(instruction, Instruction::Jmp(_) | Instruction::Jie(..) | Instruction::Jio(..) | Instruction::Jnz(..))
+}
+
+pub fn analyze(program: &[Instruction]) -> Analysis {
+    let len = program.len();
+    let mut leaders = BTreeSet::from([0, len]);
+    let mut out_of_range = vec![];
+    let mut indirect = vec![];
+    for (pc, instruction) in program.iter().enumerate() {
+        if !ends_block(instruction) {
+            continue;
+        }
+        if let Instruction::Jnz(_, Operand::Reg(_)) = instruction {
+            indirect.push(pc);
+        }
+        let targets = successors(instruction, pc);
+        leaders.insert(pc + 1);
+        for target in targets {
+            if (0..=len as i64).contains(&target) {
+                leaders.insert(target as usize);
+            } else {
+                out_of_range.push((pc, target));
+            }
+        }
+    }
+    let leaders = leaders.into_iter().collect::<Vec<_>>();
+    let mut blocks = leaders.windows(2)
+        .map(|pair| BasicBlock { start: pair[0], end: pair[1] })
+        .filter(|block| block.start < block.end)
+        .collect::<Vec<_>>();
+    blocks.push(BasicBlock { start: len, end: len });
+
+    let mut cfg = DiGraph::new();
+    for &block in &blocks {
+        cfg.add_node(block);
+    }
+    let exit = blocks.len() - 1;
+    let block_at = |address: i64| -> usize {
+        if !(0..len as i64).contains(&address) {
+            return exit;
+        }
+        blocks.iter().position(|block| block.start == address as usize).unwrap()
+    };
+    for (idx, block) in blocks.iter().enumerate().take(exit) {
+        let last = block.end - 1;
+        let targets = successors(&program[last], last).into_iter().map(block_at).collect::<BTreeSet<_>>();
+        for target in targets {
+            cfg.add_edge(NodeIndex::new(idx), NodeIndex::new(target), ());
+        }
+    }
+
+    let mut reachable = vec![false; blocks.len()];
+    let mut dfs = Dfs::new(&cfg, NodeIndex::new(0));
+    while let Some(node) = dfs.next(&cfg) {
+        reachable[node.index()] = true;
+    }
+    let mut unreachable: Vec<Range<usize>> = vec![];
+    for (idx, block) in blocks.iter().enumerate().take(exit) {
+        if reachable[idx] {
+            continue;
+        }
+        match unreachable.last_mut() {
+            Some(range) if range.end == block.start => range.end = block.end,
+            _ => unreachable.push(block.start..block.end),
+        }
+    }
+
+    let loops = find_loops(&cfg, &reachable);
+    Analysis { blocks, cfg, loops, unreachable, out_of_range, indirect }
+}
+
+/// Natural loops: a back edge `tail -> header` where the header dominates the
+/// tail, with every block that reaches the tail without passing the header.
+fn find_loops(cfg: &DiGraph<BasicBlock, ()>, reachable: &[bool]) -> Vec<Loop> {
+    if cfg.node_count() == 1 {
+        return vec![];
+    }
+    let dominators = dominators::simple_fast(cfg, NodeIndex::new(0));
+    let mut loops: Vec<Loop> = vec![];
+    for edge in cfg.edge_references() {
+        let (tail, header) = (edge.source(), edge.target());
+        if !reachable[tail.index()] {
+            continue;
+        }
+        let dominated = dominators.dominators(tail).is_some_and(|mut doms| doms.any(|dom| dom == header));
+        if !dominated {
+            continue;
+        }
+        let mut body = BTreeSet::from([header.index()]);
+        let mut stack = vec![tail];
+        while let Some(node) = stack.pop() {
+            if body.insert(node.index()) {
+                stack.extend(cfg.neighbors_directed(node, Direction::Incoming));
+            }
+        }
+        match loops.iter_mut().find(|l| l.header == header.index()) {
+            Some(existing) => {
+                existing.blocks = existing.blocks.iter().copied().chain(body).collect::<BTreeSet<_>>().into_iter().collect();
+            },
+            None => loops.push(Loop { header: header.index(), blocks: body.into_iter().collect() }),
+        }
+    }
+    loops.sort_by_key(|l| l.header);
+    loops
+}
+
+impl Display for Analysis {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let exit = self.exit();
+        for (idx, block) in self.blocks.iter().enumerate().take(exit) {
+            let successors = self.cfg.neighbors(NodeIndex::new(idx))
+                .map(|node| if node.index() == exit { "exit".to_string() } else { format!("B{}", node.index()) })
+                .collect::<BTreeSet<_>>();
+            writeln!(f, "B{idx} [{}..{}) -> {}", block.start, block.end, successors.into_iter().collect::<Vec<_>>().join(", "))?;
+        }
+        for l in &self.loops {
+            let blocks = l.blocks.iter().map(|idx| format!("B{idx}")).collect::<Vec<_>>();
+            writeln!(f, "loop at B{}: {}", l.header, blocks.join(", "))?;
+        }
+        for warning in self.warnings() {
+            writeln!(f, "{warning}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::vm::{parse_program, parse_program_with, Assembunny};
+
+    #[test]
+    fn test_analyze() {
+        // compute a, then count Collatz steps into b
+        let program = parse_program(
+            "jio a, +3\ninc a\ntpl a\njio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7"
+        ).unwrap();
+        let analysis = analyze(&program.instructions);
+        let starts = analysis.blocks.iter().map(|block| block.start).collect::<Vec<_>>();
+        assert_eq!(starts, [0, 1, 3, 4, 6, 9, 10, 11]);
+        assert_eq!(analysis.loops, vec![Loop { header: 2, blocks: vec![2, 3, 4, 5, 6] }]);
+        assert!(!analysis.has_issues());
+        assert_eq!(analysis.block_of(7), Some(4));
+
+        let program = parse_program("inc a\njmp +3\ninc b\ninc b\njmp -10").unwrap();
+        let analysis = analyze(&program.instructions);
+        assert_eq!(analysis.unreachable, vec![2..4]);
+        assert_eq!(analysis.out_of_range, vec![(4, -6)]);
+        assert!(analysis.to_string().contains("warning: jump at 4 leaves the program (target -6)"));
+
+        let program = parse_program_with("cpy 2 a\ndec a\njnz a -1\njnz 1 c\nout a", &Assembunny).unwrap();
+        let analysis = analyze(&program.instructions);
+        assert_eq!(analysis.loops, vec![Loop { header: 1, blocks: vec![1] }]);
+        assert_eq!(analysis.indirect, vec![3]);
+    }
+}
@@ -0,0 +1,139 @@
+/*
+ * Assembler / disassembler for the day 23 instruction set, with labels as jump targets.
+ * Example: `asm::assemble("loop: inc a\njmp loop")?`.
+ */
+use super::isa::{self, Day23, InstructionSet};
+use super::{Program, VmError};
+use std::collections::{BTreeSet, HashMap};
+
+/// Strips `;` comments and splits off a leading `label:`.
+fn split_line(line: &str) -> (Option<&str>, &str) {
+    let line = line.split(';').next().unwrap().trim();
+    match line.split_once(':') {
+        Some((label, rest)) if is_label(label.trim()) => (Some(label.trim()), rest.trim()),
+        _ => (None, line),
+    }
+}
+
+fn is_label(text: &str) -> bool {
+    text.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && text.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Builds a 2015 day 23 program from assembly text. Jump targets may be
+/// relative offsets (`+3`, `-7`) or labels; a label on its own line names the
+/// next instruction, or the end of the program when nothing follows. Plain
+/// puzzle input is valid assembly.
+pub fn assemble(source: &str) -> Result<Program, VmError> {
+    assemble_with(source, &Day23)
+}
+
+pub fn assemble_with(source: &str, isa: &dyn InstructionSet) -> Result<Program, VmError> {
+    let mut labels: HashMap<&str, i64> = HashMap::new();
+    let mut lines = vec![];
+    for line in source.lines() {
+        let (label, text) = split_line(line);
+        if let Some(label) = label {
+            if labels.insert(label, lines.len() as i64).is_some() {
+                return Err(VmError::DuplicateLabel(label.to_string()));
+            }
+        }
+        if !text.is_empty() {
+            lines.push(text);
+        }
+    }
+
+    let mut registers = isa.registers().iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    let instructions = lines.iter().enumerate().map(|(pc, text)| {
+        let (mnemonic, mut operands) = isa::tokenize(text).ok_or_else(|| VmError::Parse(text.to_string()))?;
+        let jump = isa.jump_operand(mnemonic).filter(|&idx| operands.get(idx).is_some_and(|o| is_label(o)));
+        let resolved;
+        match jump.map(|idx| (idx, labels.get(operands[idx]))) {
+            Some((idx, Some(address))) => {
+                resolved = format!("{:+}", address - pc as i64);
+                operands[idx] = &resolved;
+            },
+            // some instruction sets also jump by a register, but only one already in use
+            Some((idx, None)) if !registers.iter().any(|name| name == operands[idx]) => {
+                return Err(VmError::UnknownLabel(operands[idx].to_string()));
+            },
+            _ => (),
+        }
+        isa::decode_tokens(isa, text, (mnemonic, &operands), &mut registers)
+    }).collect::<Result<Vec<_>, _>>()?;
+    Ok(Program { instructions, registers })
+}
+
+/// Assembly text for `program`, with a label (`L0`, `L1`, ...) for every jump
+/// target inside the program or at its end. Other targets stay as offsets.
+pub fn disassemble(program: &Program) -> String {
+    let targets: BTreeSet<i64> = program.instructions.iter().enumerate()
+        .filter_map(|(pc, instruction)| Some(pc as i64 + instruction.jump_offset()?))
+        .filter(|target| (0..=program.len() as i64).contains(target))
+        .collect();
+    let labels: HashMap<i64, String> = targets.iter().enumerate()
+        .map(|(idx, &target)| (target, format!("L{idx}")))
+        .collect();
+
+    let mut out = String::new();
+    for pc in 0..=program.len() {
+        if let Some(label) = labels.get(&(pc as i64)) {
+            out += &format!("{label}:\n");
+        }
+        let Some(instruction) = program.instructions.get(pc) else { break };
+        let text = program.format(instruction);
+        // the jump offset is always the last operand
+        let text = match instruction.jump_offset().and_then(|offset| labels.get(&(pc as i64 + offset))) {
+            Some(label) => format!("{} {label}", text.rsplit_once(' ').unwrap().0),
+            None => text,
+        };
+        out += &format!("    {text}\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::vm::{parse_program, parse_program_with, Assembunny, Extended, Instruction, Operand, Register};
+
+    #[test]
+    fn test_assemble() {
+        let source = "start: jio a, done ; stop once a is one\n    inc b\n    jmp start\ndone:\n";
+        assert_eq!(assemble(source).unwrap().instructions, vec![
+            Instruction::Jio(Register::A, 3),
+            Instruction::Inc(Register::B),
+            Instruction::Jmp(-2),
+        ]);
+        assert_eq!(assemble("jmp nowhere"), Err(VmError::UnknownLabel("nowhere".to_string())));
+        assert_eq!(assemble("x: inc a\nx: inc b"), Err(VmError::DuplicateLabel("x".to_string())));
+
+        let input = crate::read_file("examples", 23);
+        assert_eq!(assemble(&input), parse_program(&input));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let program = parse_program("jio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7\njmp +5").unwrap();
+        let text = disassemble(&program);
+        assert_eq!(text, "L0:\n    jio a, L3\n    inc b\n    jie a, L1\n    tpl a\n    inc a\n    jmp L2\n\
+            L1:\n    hlf a\nL2:\n    jmp L0\nL3:\n    jmp +5\n");
+        assert_eq!(assemble(&text), Ok(program));
+
+        let program = parse_program_with("cpy 2 a\ndec a\njnz a -1\njnz 1 c\nout a", &Assembunny).unwrap();
+        let text = disassemble(&program);
+        assert_eq!(text, "    cpy 2 a\nL0:\n    dec a\n    jnz a L0\n    jnz 1 c\n    out a\n");
+        assert_eq!(assemble_with(&text, &Assembunny), Ok(program));
+    }
+
+    #[test]
+    fn test_unknown_label() {
+        // a typo must not become a fresh register, which would jump by zero forever
+        assert_eq!(assemble_with("top: dec a\njnz a tpo", &Assembunny), Err(VmError::UnknownLabel("tpo".to_string())));
+        assert_eq!(assemble_with("jnz a n\ncpy 2 n", &Extended), Err(VmError::UnknownLabel("n".to_string())));
+        let program = assemble_with("cpy -1 n\njnz a n", &Extended).unwrap();
+        assert_eq!(program.instructions[1], Instruction::Jnz(Operand::Reg(Register::A), Operand::Reg(Register(4))));
+        assert_eq!(assemble_with("jnz a b", &Assembunny).unwrap().instructions,
+            vec![Instruction::Jnz(Operand::Reg(Register::A), Operand::Reg(Register::B))]);
+    }
+}
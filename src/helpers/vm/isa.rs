@@ -0,0 +1,209 @@
+/*
+ * Instruction sets: which mnemonics a program may use and how they decode.
+ * An `InstructionSet` only chooses a dialect over the built-in `Instruction`s;
+ * a new opcode still needs its own variant and a case in `Machine::step`.
+ */
+use super::{Instruction, Operand, Register, Value, VmError};
+
+/// Register names of a program being decoded.
+pub struct RegisterTable<'a> {
+    names: &'a mut Vec<String>,
+    open: bool,
+}
+
+impl RegisterTable<'_> {
+    /// Looks `name` up, adding it when the instruction set allows new registers.
+    pub fn register(&mut self, name: &str) -> Result<Register, VmError> {
+        if let Some(idx) = self.names.iter().position(|known| known == name) {
+            return Ok(Register(idx));
+        }
+        if !self.open || !name.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Err(VmError::UnknownRegister(name.to_string()));
+        }
+        self.names.push(name.to_string());
+        Ok(Register(self.names.len() - 1))
+    }
+
+    /// A number, or else a register.
+    pub fn operand(&mut self, text: &str) -> Result<Operand, VmError> {
+        match text.parse::<Value>() {
+            Ok(value) => Ok(Operand::Imm(value)),
+            Err(_) => Ok(Operand::Reg(self.register(text)?)),
+        }
+    }
+}
+
+/// A text dialect decoding to the `Instruction`s the machine already runs.
+pub trait InstructionSet {
+    /// Registers every program starts with, in index order.
+    fn registers(&self) -> &[&'static str];
+
+    /// Whether other names become new registers instead of an error.
+    fn open_registers(&self) -> bool {
+        true
+    }
+
+    /// Position of the operand holding a relative jump, so the assembler can put labels there.
+    fn jump_operand(&self, mnemonic: &str) -> Option<usize>;
+
+    /// Builds one instruction. Unknown mnemonics are `UnknownOpcode`, wrong
+    /// operands are `Parse` (the caller fills in the line).
+    fn decode(&self, mnemonic: &str, operands: &[&str], registers: &mut RegisterTable) -> Result<Instruction, VmError>;
+}
+
+fn offset(text: &str) -> Result<i64, VmError> {
+    text.parse().map_err(|_| VmError::Parse(String::new()))
+}
+
+/// The six 2015 day 23 instructions over registers `a` and `b`.
+pub struct Day23;
+
+impl InstructionSet for Day23 {
+    fn registers(&self) -> &[&'static str] {
+        &["a", "b"]
+    }
+
+    fn open_registers(&self) -> bool {
+        false
+    }
+
+    fn jump_operand(&self, mnemonic: &str) -> Option<usize> {
+        match mnemonic {
+            "jmp" => Some(0),
+            "jie" | "jio" => Some(1),
+            _ => None,
+        }
+    }
+
+    fn decode(&self, mnemonic: &str, operands: &[&str], registers: &mut RegisterTable) -> Result<Instruction, VmError> {
+        match (mnemonic, operands) {
+            ("hlf", [r]) => Ok(Instruction::Hlf(registers.register(r)?)),
+            ("tpl", [r]) => Ok(Instruction::Tpl(registers.register(r)?)),
+            ("inc", [r]) => Ok(Instruction::Inc(registers.register(r)?)),
+            ("jmp", [o]) => Ok(Instruction::Jmp(offset(o)?)),
+            ("jie", [r, o]) => Ok(Instruction::Jie(registers.register(r)?, offset(o)?)),
+            ("jio", [r, o]) => Ok(Instruction::Jio(registers.register(r)?, offset(o)?)),
+            ("hlf" | "tpl" | "inc" | "jmp" | "jie" | "jio", _) => Err(VmError::Parse(String::new())),
+            _ => Err(VmError::UnknownOpcode(mnemonic.to_string())),
+        }
+    }
+}
+
+/// The 2016 `cpy`/`inc`/`dec`/`jnz`/`out` family over `a` to `d` and any other named register.
+pub struct Assembunny;
+
+impl InstructionSet for Assembunny {
+    fn registers(&self) -> &[&'static str] {
+        &["a", "b", "c", "d"]
+    }
+
+    fn jump_operand(&self, mnemonic: &str) -> Option<usize> {
+        (mnemonic == "jnz").then_some(1)
+    }
+
+    fn decode(&self, mnemonic: &str, operands: &[&str], registers: &mut RegisterTable) -> Result<Instruction, VmError> {
+        match (mnemonic, operands) {
+            ("cpy", [src, r]) => Ok(Instruction::Cpy(registers.operand(src)?, registers.register(r)?)),
+            ("inc", [r]) => Ok(Instruction::Inc(registers.register(r)?)),
+            ("dec", [r]) => Ok(Instruction::Dec(registers.register(r)?)),
+            ("jnz", [test, o]) => Ok(Instruction::Jnz(registers.operand(test)?, registers.operand(o)?)),
+            ("out", [src]) => Ok(Instruction::Out(registers.operand(src)?)),
+            ("cpy" | "inc" | "dec" | "jnz" | "out", _) => Err(VmError::Parse(String::new())),
+            _ => Err(VmError::UnknownOpcode(mnemonic.to_string())),
+        }
+    }
+}
+
+/// Both families together.
+pub struct Extended;
+
+impl InstructionSet for Extended {
+    fn registers(&self) -> &[&'static str] {
+        Assembunny.registers()
+    }
+
+    fn jump_operand(&self, mnemonic: &str) -> Option<usize> {
+        Day23.jump_operand(mnemonic).or_else(|| Assembunny.jump_operand(mnemonic))
+    }
+
+    fn decode(&self, mnemonic: &str, operands: &[&str], registers: &mut RegisterTable) -> Result<Instruction, VmError> {
+        match Day23.decode(mnemonic, operands, registers) {
+            Err(VmError::UnknownOpcode(_)) => Assembunny.decode(mnemonic, operands, registers),
+            decoded => decoded,
+        }
+    }
+}
+
+/// Looks an instruction set up by name: `day23`, `assembunny` or `extended`.
+pub fn by_name(name: &str) -> Option<&'static dyn InstructionSet> {
+    match name {
+        "day23" => Some(&Day23),
+        "assembunny" => Some(&Assembunny),
+        "extended" => Some(&Extended),
+        _ => None,
+    }
+}
+
+/// Splits `hlf a`, `jie a, +4` or `cpy 41 a` into the mnemonic and its operands.
+pub fn tokenize(line: &str) -> Option<(&str, Vec<&str>)> {
+    let mut tokens = line.split([' ', ',', '\t']).filter(|token| !token.is_empty());
+    let mnemonic = tokens.next()?;
+    Some((mnemonic, tokens.collect()))
+}
+
+pub fn decode_tokens(
+    isa: &dyn InstructionSet,
+    line: &str,
+    (mnemonic, operands): (&str, &[&str]),
+    registers: &mut Vec<String>,
+) -> Result<Instruction, VmError> {
+    let mut table = RegisterTable { names: registers, open: isa.open_registers() };
+    isa.decode(mnemonic, operands, &mut table).map_err(|e| match e {
+        VmError::Parse(_) => VmError::Parse(line.trim().to_string()),
+        e => e,
+    })
+}
+
+pub fn decode_line(isa: &dyn InstructionSet, line: &str, registers: &mut Vec<String>) -> Result<Instruction, VmError> {
+    let (mnemonic, operands) = tokenize(line).ok_or_else(|| VmError::Parse(line.to_string()))?;
+    decode_tokens(isa, line, (mnemonic, &operands), registers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode(isa: &dyn InstructionSet, line: &str) -> (Result<Instruction, VmError>, Vec<String>) {
+        let mut registers = isa.registers().iter().map(|name| name.to_string()).collect();
+        (decode_line(isa, line, &mut registers), registers)
+    }
+
+    #[test]
+    fn test_lookup_and_tokenize() {
+        assert_eq!(by_name("day23").map(|isa| isa.registers()), Some(&["a", "b"][..]));
+        assert_eq!(by_name("assembunny").map(|isa| isa.jump_operand("jnz")), Some(Some(1)));
+        assert_eq!(by_name("extended").map(|isa| isa.open_registers()), Some(true));
+        assert!(by_name("intcode").is_none());
+        assert_eq!(tokenize("  jie a, +4"), Some(("jie", vec!["a", "+4"])));
+        assert_eq!(tokenize("cpy\t41 a"), Some(("cpy", vec!["41", "a"])));
+        assert_eq!(tokenize(" , "), None);
+    }
+
+    #[test]
+    fn test_registers_and_fallback() {
+        // day 23 has exactly two registers
+        assert_eq!(decode(&Day23, "inc c").0, Err(VmError::UnknownRegister("c".to_string())));
+        let (decoded, registers) = decode(&Assembunny, "inc c2");
+        assert_eq!((decoded, registers.len()), (Err(VmError::UnknownRegister("c2".to_string())), 4));
+        let (decoded, registers) = decode(&Assembunny, "dec n");
+        assert_eq!((decoded, registers.last().map(String::as_str)), (Ok(Instruction::Dec(Register(4))), Some("n")));
+
+        // day 23 decodes first; its operand errors do not fall through to assembunny
+        assert_eq!(decode(&Extended, "inc b").0, Ok(Instruction::Inc(Register::B)));
+        assert_eq!(decode(&Extended, "jmp -2").0, Ok(Instruction::Jmp(-2)));
+        assert_eq!(decode(&Extended, "inc a b").0, Err(VmError::Parse("inc a b".to_string())));
+        assert_eq!(decode(&Extended, "jnz 1 -2").0, Ok(Instruction::Jnz(Operand::Imm(1), Operand::Imm(-2))));
+        assert_eq!(Extended.jump_operand("jio"), Some(1));
+        assert_eq!(decode(&Extended, "mul a b").0, Err(VmError::UnknownOpcode("mul".to_string())));
+    }
+}
@@ -0,0 +1,212 @@
+/*
+ * Pattern optimizer: recognises well-known loops and straight-line arithmetic
+ * and computes their effect directly instead of walking every instruction.
+ * Example: `machine.set_kernels(optimize::optimize(machine.program()));`
+ */
+use super::{Instruction, Operand, Program, Register, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kernel {
+    /// A run of `inc r` / `tpl r`, i.e. `r = r * mul + add`.
+    Affine { register: Register, mul: Value, add: Value, len: usize },
+    /// Counts Collatz steps of `value` down to 1 into `count`:
+    /// `jio v, +8; inc c; jie v, +4; tpl v; inc v; jmp +2; hlf v; jmp -7`.
+    Collatz { value: Register, count: Register },
+    /// `inc x; dec y; jnz y -2`, i.e. `x += y; y = 0`.
+    Add { target: Register, source: Register },
+    /// `cpy f s; inc x; dec s; jnz s -2; dec n; jnz n -5`, i.e. `x += f * n`.
+    Mul { target: Register, factor: Operand, scratch: Register, counter: Register },
+}
+
+/// What running a kernel once amounts to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Effect {
+    pub writes: Vec<(Register, Value)>,
+    /// Instructions the interpreter would have executed.
+    pub steps: u64,
+    /// Distance to the instruction after the kernel.
+    pub jump: i64,
+}
+
+impl Kernel {
+    /// Number of instructions the kernel covers.
+    pub fn span(&self) -> usize {
+        match self {
+            Kernel::Affine { len, .. } => *len,
+            Kernel::Collatz { .. } => 8,
+            Kernel::Add { .. } => 3,
+            Kernel::Mul { .. } => 6,
+        }
+    }
+
+    /// The kernel's effect on `registers`, or `None` when the loop would not
+    /// terminate normally (or overflows) and must be interpreted instead.
+    pub fn apply(&self, registers: &[Value]) -> Option<Effect> {
+        match *self {
+            Kernel::Affine { register, mul, add, len } => {
+                let value = registers[register.0].checked_mul(mul)?.checked_add(add)?;
+                Some(Effect { writes: vec![(register, value)], steps: len as u64, jump: len as i64 })
+            },
+            Kernel::Collatz { value, count } => {
+                let (mut v, mut c, mut steps) = (registers[value.0], registers[count.0], 1);
+                if v < 1 {
+                    return None;
+                }
+                while v != 1 {
+                    c = c.checked_add(1)?;
+                    if v % 2 == 0 {
+                        v /= 2;
+                        steps += 5;
+                    } else {
+                        v = v.checked_mul(3)?.checked_add(1)?;
+                        steps += 7;
+                    }
+                }
+                Some(Effect { writes: vec![(value, v), (count, c)], steps, jump: 8 })
+            },
+            Kernel::Add { target, source } => {
+                let y = registers[source.0];
+                if y < 1 {
+                    return None;
+                }
+                let x = registers[target.0].checked_add(y)?;
+                Some(Effect { writes: vec![(target, x), (source, 0)], steps: 3 * y as u64, jump: 3 })
+            },
+            Kernel::Mul { target, factor, scratch, counter } => {
+                let f = match factor {
+                    Operand::Reg(r) => registers[r.0],
+                    Operand::Imm(value) => value,
+                };
+                let n = registers[counter.0];
+                if f < 1 || n < 1 {
+                    return None;
+                }
+                let x = registers[target.0].checked_add(f.checked_mul(n)?)?;
+                let steps = (n as u64).checked_mul(3 * f as u64 + 3)?;
+                Some(Effect { writes: vec![(target, x), (scratch, 0), (counter, 0)], steps, jump: 6 })
+            },
+        }
+    }
+}
+
+fn match_collatz(code: &[Instruction]) -> Option<Kernel> {
+    use Instruction::*;
+    match *code.get(..8)? {
+        [Jio(v0, 8), Inc(c), Jie(v1, 4), Tpl(v2), Inc(v3), Jmp(2), Hlf(v4), Jmp(-7)]
+            if [v1, v2, v3, v4].iter().all(|&v| v == v0) && c != v0 =>
+        {
+            Some(Kernel::Collatz { value: v0, count: c })
+        },
+        _ => None,
+    }
+}
+
+fn match_add(code: &[Instruction]) -> Option<Kernel> {
+    use Instruction::*;
+    match *code.get(..3)? {
+        [Inc(x), Dec(y), Jnz(Operand::Reg(y1), Operand::Imm(-2))]
+        | [Dec(y), Inc(x), Jnz(Operand::Reg(y1), Operand::Imm(-2))]
+            if y == y1 && x != y =>
+        {
+            Some(Kernel::Add { target: x, source: y })
+        },
+        _ => None,
+    }
+}
+
+fn match_mul(code: &[Instruction]) -> Option<Kernel> {
+    use Instruction::*;
+    let Kernel::Add { target, source: scratch } = match_add(code.get(1..)?)? else { return None };
+    match *code.get(..6)? {
+        [Cpy(factor, s), _, _, _, Dec(n), Jnz(Operand::Reg(n1), Operand::Imm(-5))]
+            if s == scratch && n == n1 && n != target && n != scratch && factor != Operand::Reg(n)
+                && factor != Operand::Reg(scratch) && factor != Operand::Reg(target) =>
+        {
+            Some(Kernel::Mul { target, factor, scratch, counter: n })
+        },
+        _ => None,
+    }
+}
+
+fn match_affine(code: &[Instruction]) -> Option<Kernel> {
+    let register = match code.first()? {
+        Instruction::Inc(r) | Instruction::Tpl(r) => *r,
+        _ => return None,
+    };
+    let (mut mul, mut add, mut len) = (1 as Value, 0 as Value, 0);
+    for instruction in code {
+        match *instruction {
+            Instruction::Inc(r) if r == register => add += 1,
+            Instruction::Tpl(r) if r == register => (mul, add) = (mul.checked_mul(3)?, add.checked_mul(3)?),
+            _ => break,
+        }
+        len += 1;
+    }
+    (len > 1).then_some(Kernel::Affine { register, mul, add, len })
+}
+
+/// One slot per instruction, holding the kernel that starts there. Kernels only
+/// fire when execution reaches their first instruction; jumping into the middle
+/// of one still runs the original code.
+pub fn optimize(program: &Program) -> Vec<Option<Kernel>> {
+    let code = &program.instructions;
+    (0..code.len())
+        .map(|pc| {
+            let rest = &code[pc..];
+            match_collatz(rest)
+                .or_else(|| match_mul(rest))
+                .or_else(|| match_add(rest))
+                .or_else(|| match_affine(rest))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::helpers::vm::{parse_program, parse_program_with, Assembunny, Machine, Stop};
+
+    /// Day 23 shaped: build a different `a` per part, then count its Collatz steps.
+    const DAY23: &str = "jio a, +8\ninc a\ntpl a\ninc a\ninc a\ntpl a\ntpl a\njmp +5\ntpl a\ninc a\ntpl a\ninc a\ninc a\n\
+        jio a, +8\ninc b\njie a, +4\ntpl a\ninc a\njmp +2\nhlf a\njmp -7";
+
+    fn run(program: &Program, start: &[(Register, Value)], optimized: bool) -> Machine {
+        let mut machine = Machine::new(program.clone());
+        start.iter().for_each(|&(r, value)| machine.set_register(r, value));
+        if optimized {
+            machine.set_kernels(optimize(program));
+        }
+        assert_eq!(machine.run(), Ok(Stop::Halted));
+        machine
+    }
+
+    #[test]
+    fn test_collatz() {
+        let program = parse_program(DAY23).unwrap();
+        let kernels = optimize(&program);
+        assert_eq!(kernels[13], Some(Kernel::Collatz { value: Register::A, count: Register::B }));
+        assert_eq!(kernels[4], Some(Kernel::Affine { register: Register::A, mul: 9, add: 9, len: 3 }));
+
+        for a in 0..50 {
+            let interpreted = run(&program, &[(Register::A, a)], false);
+            let optimized = run(&program, &[(Register::A, a)], true);
+            assert_eq!(optimized.registers, interpreted.registers);
+            assert_eq!(optimized.steps(), interpreted.steps());
+        }
+    }
+
+    #[test]
+    fn test_add_and_mul() {
+        let program = parse_program_with("cpy 7 b\ncpy 6 d\ncpy b c\ninc a\ndec c\njnz c -2\ndec d\njnz d -5\n\
+            cpy 5 c\ndec c\ninc a\njnz c -2", &Assembunny).unwrap();
+        let kernels = optimize(&program);
+        assert!(matches!(kernels[2], Some(Kernel::Mul { .. })));
+        assert!(matches!(kernels[9], Some(Kernel::Add { .. })));
+
+        let interpreted = run(&program, &[], false);
+        let optimized = run(&program, &[], true);
+        assert_eq!(optimized.register_named("a"), Ok(47));
+        assert_eq!(optimized.registers, interpreted.registers);
+        assert_eq!(optimized.steps(), interpreted.steps());
+    }
+}
@@ -0,0 +1,336 @@
+/*
+ * Wizard against boss: the whole fight fits in a small `Copy` state, so the
+ * cheapest win is a Dijkstra search over mana spent, remembering the best
+ * cost of every state it reaches and the spell that got there.
+ * Example: `Battle::new(Wizard::default(), Boss::parse(input)?).cheapest_win()`.
+ */
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::fmt::Display;
+
+lazy_static! {
+    static ref STAT_REGEX: Regex = Regex::new(r"^(?<stat>Hit Points|Damage): (?<value>\d+)$").unwrap();
+}
+
+const SHIELD_ARMOR: u32 = 7;
+const POISON_DAMAGE: u32 = 3;
+const RECHARGE_MANA: u32 = 101;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WizardError {
+    Parse { line: usize, text: String },
+    MissingStat(String),
+}
+
+impl Display for WizardError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WizardError::Parse { line, text } => write!(f, "line {line}: cannot parse `{text}`"),
+            WizardError::MissingStat(stat) => write!(f, "no `{stat}` given"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Spell {
+    MagicMissile,
+    Drain,
+    Shield,
+    Poison,
+    Recharge,
+}
+
+impl Spell {
+    pub const ALL: [Spell; 5] = [Spell::MagicMissile, Spell::Drain, Spell::Shield, Spell::Poison, Spell::Recharge];
+
+    pub fn cost(&self) -> u32 {
+        match self {
+            Spell::MagicMissile => 53,
+            Spell::Drain => 73,
+            Spell::Shield => 113,
+            Spell::Poison => 173,
+            Spell::Recharge => 229,
+        }
+    }
+}
+
+impl Display for Spell {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Spell::MagicMissile => write!(f, "Magic Missile"),
+            Spell::Drain => write!(f, "Drain"),
+            Spell::Shield => write!(f, "Shield"),
+            Spell::Poison => write!(f, "Poison"),
+            Spell::Recharge => write!(f, "Recharge"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wizard {
+    pub hp: u32,
+    pub mana: u32,
+}
+
+impl Default for Wizard {
+    fn default() -> Wizard {
+        Wizard { hp: 50, mana: 500 }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Boss {
+    pub hp: u32,
+    pub damage: u32,
+}
+
+impl Boss {
+    /// `Hit Points: n` and `Damage: n`, one per line.
+    pub fn parse(input: &str) -> Result<Boss, WizardError> {
+        let (mut hp, mut damage) = (None, None);
+        for (idx, line) in input.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let error = || WizardError::Parse { line: idx + 1, text: line.to_string() };
+            let captured = STAT_REGEX.captures(line.trim()).ok_or_else(error)?;
+            let value = captured["value"].parse().map_err(|_| error())?;
+            match &captured["stat"] {
+                "Hit Points" => hp = Some(value),
+                _ => damage = Some(value),
+            }
+        }
+        Ok(Boss {
+            hp: hp.ok_or_else(|| WizardError::MissingStat("Hit Points".to_string()))?,
+            damage: damage.ok_or_else(|| WizardError::MissingStat("Damage".to_string()))?,
+        })
+    }
+}
+
+/// Everything that changes during a fight, at the start of a player's turn.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct State {
+    pub hp: u32,
+    pub mana: u32,
+    pub boss_hp: u32,
+    /// Turns left on each effect.
+    pub shield: u8,
+    pub poison: u8,
+    pub recharge: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Won,
+    Lost,
+    Ongoing(State),
+}
+
+impl State {
+    /// Effects tick at the start of both turns.
+    fn tick(mut self) -> Outcome {
+        if self.poison > 0 {
+            self.boss_hp = self.boss_hp.saturating_sub(POISON_DAMAGE);
+            self.poison -= 1;
+        }
+        if self.recharge > 0 {
+            self.mana = self.mana.saturating_add(RECHARGE_MANA);
+            self.recharge -= 1;
+        }
+        self.shield = self.shield.saturating_sub(1);
+        match self.boss_hp {
+            0 => Outcome::Won,
+            _ => Outcome::Ongoing(self),
+        }
+    }
+}
+
+/// The cheapest win found and the spells cast for it, in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Victory {
+    pub mana: u32,
+    pub spells: Vec<Spell>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Node {
+    Fight(State),
+    /// Won by casting the spell from the state.
+    Won(State, Spell),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Battle {
+    pub player: Wizard,
+    pub boss: Boss,
+    /// The player loses a hit point at the start of each of their turns.
+    pub hard: bool,
+}
+
+impl Battle {
+    pub fn new(player: Wizard, boss: Boss) -> Battle {
+        Battle { player, boss, hard: false }
+    }
+
+    pub fn hard_mode(mut self) -> Battle {
+        self.hard = true;
+        self
+    }
+
+    pub fn start(&self) -> State {
+        State { hp: self.player.hp, mana: self.player.mana, boss_hp: self.boss.hp, shield: 0, poison: 0, recharge: 0 }
+    }
+
+    /// The start of the player's turn: hard mode's toll, then effects.
+    fn begin_turn(&self, mut state: State) -> Outcome {
+        if self.hard {
+            state.hp = state.hp.saturating_sub(1);
+            if state.hp == 0 {
+                return Outcome::Lost;
+            }
+        }
+        state.tick()
+    }
+
+    /// Casts `spell` after `begin_turn`, then plays the boss's turn; `None`
+    /// when the spell cannot be cast.
+    fn cast(&self, mut state: State, spell: Spell) -> Option<Outcome> {
+        state.mana = state.mana.checked_sub(spell.cost())?;
+        match spell {
+            Spell::MagicMissile => state.boss_hp = state.boss_hp.saturating_sub(4),
+            Spell::Drain => {
+                state.boss_hp = state.boss_hp.saturating_sub(2);
+                state.hp = state.hp.saturating_add(2);
+            },
+            Spell::Shield if state.shield == 0 => state.shield = 6,
+            Spell::Poison if state.poison == 0 => state.poison = 6,
+            Spell::Recharge if state.recharge == 0 => state.recharge = 5,
+            _ => return None,
+        }
+        if state.boss_hp == 0 {
+            return Some(Outcome::Won);
+        }
+        let armor = if state.shield > 0 { SHIELD_ARMOR } else { 0 };
+        let Outcome::Ongoing(mut state) = state.tick() else { return Some(Outcome::Won) };
+        state.hp = state.hp.saturating_sub(self.boss.damage.saturating_sub(armor).max(1));
+        Some(if state.hp == 0 { Outcome::Lost } else { Outcome::Ongoing(state) })
+    }
+
+    /// The win spending the least mana, by Dijkstra over fight states. A
+    /// player who cannot cast anything loses.
+    pub fn cheapest_win(&self) -> Option<Victory> {
+        let start = self.start();
+        let mut spent = HashMap::from([(start, 0)]);
+        let mut came_from: HashMap<State, (State, Spell)> = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, Node::Fight(start)))]);
+        let path = |came_from: &HashMap<State, (State, Spell)>, mut state: State| {
+            let mut spells = vec![];
+            while let Some(&(previous, spell)) = came_from.get(&state) {
+                spells.push(spell);
+                state = previous;
+            }
+            spells.reverse();
+            spells
+        };
+        while let Some(Reverse((mana, node))) = queue.pop() {
+            let state = match node {
+                Node::Won(state, spell) => {
+                    let mut spells = path(&came_from, state);
+                    spells.push(spell);
+                    return Some(Victory { mana, spells });
+                },
+                Node::Fight(state) if spent.get(&state).is_some_and(|&best| best < mana) => continue,
+                Node::Fight(state) => state,
+            };
+            let turn = match self.begin_turn(state) {
+                Outcome::Won => return Some(Victory { mana, spells: path(&came_from, state) }),
+                Outcome::Lost => continue,
+                Outcome::Ongoing(turn) => turn,
+            };
+            for spell in Spell::ALL {
+                let cost = mana + spell.cost();
+                match self.cast(turn, spell) {
+                    Some(Outcome::Won) => queue.push(Reverse((cost, Node::Won(state, spell)))),
+                    Some(Outcome::Ongoing(next)) if spent.get(&next).is_none_or(|&best| cost < best) => {
+                        spent.insert(next, cost);
+                        came_from.insert(next, (state, spell));
+                        queue.push(Reverse((cost, Node::Fight(next))));
+                    },
+                    _ => (),
+                }
+            }
+        }
+        None
+    }
+
+    /// The fight `spells` make, one line per turn, ending with who won; `None`
+    /// if a spell cannot be cast when its turn comes.
+    pub fn replay(&self, spells: &[Spell]) -> Option<Vec<String>> {
+        let mut state = self.start();
+        let mut log = vec![];
+        for &spell in spells {
+            let turn = match self.begin_turn(state) {
+                Outcome::Ongoing(turn) => turn,
+                Outcome::Won => break,
+                Outcome::Lost => {
+                    log.push("The player dies.".to_string());
+                    return Some(log);
+                },
+            };
+            log.push(format!("{:>3} hp {:>4} mana | boss {:>3} hp | casts {spell}", turn.hp, turn.mana, turn.boss_hp));
+            match self.cast(turn, spell)? {
+                Outcome::Ongoing(next) => state = next,
+                Outcome::Won => {
+                    log.push("The boss dies.".to_string());
+                    return Some(log);
+                },
+                Outcome::Lost => {
+                    log.push("The player dies.".to_string());
+                    return Some(log);
+                },
+            }
+        }
+        log.push(match self.begin_turn(state) {
+            Outcome::Won => "The boss dies.".to_string(),
+            Outcome::Lost => "The player dies.".to_string(),
+            Outcome::Ongoing(_) => "The fight goes on.".to_string(),
+        });
+        Some(log)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_examples() {
+        let player = Wizard { hp: 10, mana: 250 };
+        let battle = Battle::new(player, Boss::parse("Hit Points: 13\nDamage: 8\n").unwrap());
+        let victory = battle.cheapest_win().unwrap();
+        assert_eq!((victory.mana, victory.spells.as_slice()), (226, &[Spell::Poison, Spell::MagicMissile][..]));
+        assert_eq!(battle.replay(&victory.spells).unwrap().last().map(String::as_str), Some("The boss dies."));
+        assert_eq!(battle.hard_mode().cheapest_win(), None);
+
+        let battle = Battle::new(player, Boss { hp: 14, damage: 8 });
+        let victory = battle.cheapest_win().unwrap();
+        assert_eq!(victory.mana, 641);
+        assert_eq!(victory.spells.iter().map(Spell::cost).sum::<u32>(), 641);
+        assert_eq!(Boss::parse("Hit Points: 13"), Err(WizardError::MissingStat("Damage".to_string())));
+    }
+
+    #[test]
+    fn test_hard_mode() {
+        let battle = Battle::new(Wizard::default(), Boss { hp: 51, damage: 9 });
+        assert_eq!(battle.cheapest_win().map(|victory| victory.mana), Some(900));
+        let hard = battle.hard_mode().cheapest_win().unwrap();
+        assert_eq!(hard.mana, 1216);
+        assert_eq!(battle.hard_mode().replay(&hard.spells).unwrap().last().map(String::as_str), Some("The boss dies."));
+    }
+
+    #[test]
+    fn test_huge_stats() {
+        let battle = Battle::new(Wizard { hp: u32::MAX, mana: u32::MAX }, Boss { hp: 6, damage: 1 });
+        let spells = [Spell::Recharge, Spell::Drain, Spell::Drain, Spell::Drain];
+        assert_eq!(battle.replay(&spells).unwrap().last().map(String::as_str), Some("The boss dies."));
+    }
+}